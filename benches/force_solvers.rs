@@ -0,0 +1,86 @@
+// Times one force evaluation of each solver on galaxy-like disks of growing size,
+// along with the direct sum done pair by pair as it was before bodies were
+// stored as arrays. Run with "cargo bench --bench force_solvers".
+
+use std::time::{Duration, Instant};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use Wallfacer::{
+    fmm::Fmm,
+    parallel::map_bodies,
+    physics::{calc_point_accel, DirectSum, ForceSolver, GravityModel},
+    quadtree::BarnesHut,
+    scalar::{Float, Vector},
+};
+
+fn disk(n: usize) -> (Vec<Vector>, Vec<Float>) {
+    let mut rng = StdRng::seed_from_u64(1);
+    let pos = (0..n)
+        .map(|_| {
+            let r: Float = 400.0 * rng.gen::<Float>().sqrt();
+            let angle: Float = rng.gen_range(0.0..std::f64::consts::TAU as Float);
+            Vector::new(r * angle.cos(), r * angle.sin())
+        })
+        .collect();
+    let mass = vec![1e6; n];
+    (pos, mass)
+}
+
+// the direct sum one pair at a time, what the vectorised kernel replaced
+struct PerPair;
+
+impl ForceSolver for PerPair {
+    fn accelerations(&self, gravity: &GravityModel, pos: &[Vector], mass: &[Float]) -> Vec<Vector> {
+        map_bodies(pos.len(), |i| {
+            let mut accel = Vector::ZERO;
+            for (j, (&source, &m)) in pos.iter().zip(mass).enumerate() {
+                if j != i {
+                    accel += calc_point_accel(gravity, pos[i], source, m);
+                }
+            }
+            accel
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        "Per pair"
+    }
+}
+
+fn time(solver: &dyn ForceSolver, pos: &[Vector], mass: &[Float]) -> Duration {
+    // best of a few runs
+    (0..3)
+        .map(|_| {
+            let start = Instant::now();
+            std::hint::black_box(solver.accelerations(&GravityModel::default(), pos, mass));
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn main() {
+    let solvers: Vec<Box<dyn ForceSolver>> = vec![
+        Box::new(PerPair),
+        Box::new(DirectSum),
+        Box::new(BarnesHut::new(0.5)),
+        Box::new(Fmm::new(4)),
+    ];
+    println!(
+        "{:>8} {:>16} {:>16} {:>16} {:>16}",
+        "bodies", "per pair", "direct", "barnes-hut", "fmm"
+    );
+    for n in [1_000, 4_000, 16_000, 64_000] {
+        let (pos, mass) = disk(n);
+        let mut row = format!("{:>8}", n);
+        for solver in solvers.iter() {
+            let direct = [PerPair.name(), DirectSum.name()].contains(&solver.name());
+            if n > 16_000 && direct {
+                row += &format!(" {:>16}", "-");
+                continue;
+            }
+            row += &format!(" {:>16.2?}", time(solver.as_ref(), &pos, &mass));
+        }
+        println!("{}", row);
+    }
+}
@@ -0,0 +1,83 @@
+// Energy error of Wisdom-Holman against leapfrog on the inner solar system for
+// a range of steps per orbit of Mercury. Run with "cargo bench --bench wisdom_holman".
+
+use std::time::Instant;
+
+use Wallfacer::{
+    integrator::{Integrator, Leapfrog},
+    orbit::OrbitalElements,
+    planet::{Planet, PlanetColor},
+    scalar::{Float, Vector},
+    system::PlanetSystem,
+    units::UnitSystem,
+    wisdom_holman::WisdomHolman,
+};
+
+const ORBITS: Float = 100.0;
+const MERCURY_PERIOD: Float = 87.969;
+
+fn inner_solar_system(steps_per_orbit: Float) -> PlanetSystem<'static> {
+    let units = UnitSystem::AstronomicalDays;
+    let gravity = units.gravity();
+    let sun = Planet::new(
+        "Sun",
+        Vector::ZERO,
+        0.00465,
+        Vector::ZERO,
+        1.0,
+        PlanetColor::white(),
+    );
+    // name, semi-major axis, eccentricity, longitude of perihelion, mass
+    let planets: [(&str, Float, Float, Float, Float); 4] = [
+        ("Mercury", 0.387, 0.2056, 1.35, 1.66e-7),
+        ("Venus", 0.723, 0.0068, 2.30, 2.45e-6),
+        ("Earth", 1.0, 0.0167, 1.80, 3.0e-6),
+        ("Mars", 1.524, 0.0934, 5.87, 3.23e-7),
+    ];
+    let mut bodies = vec![sun];
+    for (k, (name, a, e, perihelion, mass)) in planets.into_iter().enumerate() {
+        let elements = OrbitalElements::new(a, e, perihelion, k as Float);
+        bodies.push(Planet::on_orbit(
+            &gravity,
+            &sun,
+            name,
+            1e-5,
+            mass,
+            PlanetColor::white(),
+            &elements,
+        ));
+    }
+    let mut system = PlanetSystem::from_vec(MERCURY_PERIOD / steps_per_orbit, bodies);
+    system.set_units(units);
+    system
+}
+
+// worst relative energy error over the run and the time it took
+fn run(steps_per_orbit: Float, integrator: Box<dyn Integrator>) -> (Float, f64) {
+    let mut system = inner_solar_system(steps_per_orbit);
+    system.set_integrator(integrator);
+    let initial = system.diagnostics().energy();
+    let mut worst: Float = 0.0;
+    let start = Instant::now();
+    for _ in 0..(ORBITS * steps_per_orbit) as usize {
+        system.update_system();
+        worst = worst.max(((system.diagnostics().energy() - initial) / initial).abs());
+    }
+    (worst, start.elapsed().as_secs_f64() * 1e3)
+}
+
+fn main() {
+    println!("inner solar system, {} orbits of Mercury", ORBITS);
+    println!(
+        "{:>15} {:>16} {:>12} {:>16} {:>12}",
+        "steps / orbit", "leapfrog dE/E", "ms", "wisdom-holman", "ms"
+    );
+    for steps in [5.0, 10.0, 20.0, 50.0, 100.0, 200.0, 500.0, 1000.0] {
+        let (leapfrog, leapfrog_ms) = run(steps, Box::new(Leapfrog));
+        let (wh, wh_ms) = run(steps, Box::new(WisdomHolman::new()));
+        println!(
+            "{:>15} {:>16.2e} {:>12.1} {:>16.2e} {:>12.1}",
+            steps, leapfrog, leapfrog_ms, wh, wh_ms
+        );
+    }
+}
@@ -5,10 +5,9 @@ use winit::{
     window::WindowBuilder,
 };
 
-use glam::f32::Vec2;
 use pixels::wgpu::Color;
 
-use Wallfacer::{physics::*, planet::*, system::*, util::*};
+use Wallfacer::{integrator::*, physics::*, planet::*, scalar::Vector, system::*, util::*};
 
 fn main() {
     let event_loop = EventLoop::new();
@@ -23,36 +22,37 @@ fn main() {
 
     let mut planet = Planet::new(
         "Earth",
-        Vec2::new(200.0, 200.0),
+        Vector::new(200.0, 200.0),
         5.0,
-        Vec2::new(0.0, 0.0),
+        Vector::new(0.0, 0.0),
         100_000.1,
         PlanetColor::red(),
     );
 
     // good values between two planets are 1.1 and  10000000000000.0
-    // let mut planet2 = Planet {pos: Vec2::new(400.0, 300.0), radius: 10.0, vel: Vec2::new(0.05, 0.0), mass: 10_000_000_000_000_0.0};
+    // let mut planet2 = Planet {pos: Vector::new(400.0, 300.0), radius: 10.0, vel: Vector::new(0.05, 0.0), mass: 10_000_000_000_000_0.0};
     let planet2 = Planet::new(
         "Sun",
-        Vec2::new(400.0, 300.0),
+        Vector::new(400.0, 300.0),
         10.0,
-        Vec2::new(0.0, 0.0),
+        Vector::new(0.0, 0.0),
         10_000_000_000.0,
         PlanetColor::white(),
     );
-    let init_vel = calc_init_orbital_velocity(&planet, &planet2);
+    let gravity = GravityModel::default();
+    let init_vel = calc_init_orbital_velocity(&gravity, &planet, &planet2);
     planet.vel = init_vel;
 
     println!(
         "Will escape orbit: {}",
-        check_escape_velocity(&planet, &planet2)
+        check_escape_velocity(&gravity, &planet, &planet2)
     );
 
     let planet3 = Planet::new(
         "Moon",
-        Vec2::new(500.0, 500.0),
+        Vector::new(500.0, 500.0),
         3.0,
-        Vec2::new(0.0, 0.0),
+        Vector::new(0.0, 0.0),
         1000.5,
         PlanetColor::blue(),
     );
@@ -61,11 +61,19 @@ fn main() {
 
     println!(
         "Will escape orbit: {}",
-        check_escape_velocity(&planet3, &planet2)
+        check_escape_velocity(&gravity, &planet3, &planet2)
     );
 
-    let planet4 = Planet::create_satellite(&planet2, "Satellite", 8.0, 400.0, PlanetColor::green());
+    let planet4 = Planet::create_satellite(
+        &gravity,
+        &planet2,
+        "Satellite",
+        8.0,
+        400.0,
+        PlanetColor::green(),
+    );
     let planet5 = Planet::create_satellite(
+        &gravity,
         &planet,
         "Tiny Satellite",
         2.0,
@@ -79,6 +87,13 @@ fn main() {
 
     let mut planet_list =
         PlanetSystem::from_vec(5.5, vec![planet, planet2, planet3, planet4, planet5]);
+    // long runs need a high order symplectic scheme, e.g. "cargo run --bin chaotic_system -- yoshida6"
+    let scheme = std::env::args().nth(1).unwrap_or("yoshida4".to_string());
+    match integrator_from_name(&scheme) {
+        Some(integrator) => planet_list.set_integrator(integrator),
+        None => println!("Unknown integrator '{}', using the default", scheme),
+    }
+    println!("Integrator: {}", planet_list.integrator().name());
 
     event_loop.run(move |event, _, control_flow| match event {
         Event::MainEventsCleared => {
@@ -5,8 +5,7 @@ use winit::{
     window::WindowBuilder,
 };
 
-use glam::f32::Vec2;
-use Wallfacer::{planet::*, system::*, util::*};
+use Wallfacer::{integrator::*, planet::*, scalar::Vector, system::*, util::*};
 
 fn main() {
     let event_loop = EventLoop::new();
@@ -22,21 +21,24 @@ fn main() {
 
     let target = Planet::new(
         "Target",
-        Vec2::new(500.0, 400.0),
+        Vector::new(500.0, 400.0),
         10.0,
-        Vec2::new(0.0, 0.0),
+        Vector::new(0.0, 0.0),
         10_000_000.0,
         PlanetColor::white(),
     );
     let satellite = Planet::new(
         "Satellite",
-        Vec2::new(200.0, 100.0),
+        Vector::new(200.0, 100.0),
         5.0,
-        Vec2::new(1.0, 1.0) * 90.0,
+        Vector::new(1.0, 1.0) * 90.0,
         10_000_000.0,
         PlanetColor::blue(),
     );
     let mut planet_list = PlanetSystem::from_vec(0.1, vec![target, satellite]);
+    // refine the step during the close pass instead of taking it in one jump
+    planet_list.set_integrator(Box::new(VelocityVerlet));
+    planet_list.set_timestep_mode(TimestepMode::adaptive(0.0001, 0.1));
 
     event_loop.run(move |event, _, control_flow| match event {
         Event::MainEventsCleared => {
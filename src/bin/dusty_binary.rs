@@ -0,0 +1,79 @@
+use winit::{
+    dpi::LogicalSize,
+    event::{Event, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
+    window::WindowBuilder,
+};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use Wallfacer::{
+    integrator::Leapfrog,
+    planet::*,
+    scalar::{Float, Vector},
+    system::*,
+    units::{UnitSystem, Viewport},
+    util::*,
+};
+
+fn main() {
+    let event_loop = EventLoop::new();
+    let window = {
+        let size = LogicalSize::new(WIDTH as u32, HEIGHT as u32);
+        WindowBuilder::new()
+            .with_title("Dust around a binary")
+            .with_inner_size(size)
+            .build(&event_loop)
+            .unwrap()
+    };
+    let mut pixels = create_pixel_buffer(&window, WIDTH as u32, HEIGHT as u32);
+
+    // two equal stars a unit apart on a circular orbit, in N-body units
+    let stars = vec![
+        Planet::new(
+            "Star A",
+            Vector::new(-0.5, 0.0),
+            0.05,
+            Vector::new(0.0, -0.5),
+            0.5,
+            PlanetColor::new(255, 220, 120, 255),
+        ),
+        Planet::new(
+            "Star B",
+            Vector::new(0.5, 0.0),
+            0.05,
+            Vector::new(0.0, 0.5),
+            0.5,
+            PlanetColor::new(255, 160, 90, 255),
+        ),
+    ];
+    let mut planet_list = PlanetSystem::from_vec(0.01, stars);
+    planet_list.set_units(UnitSystem::NBody);
+    planet_list.set_integrator(Box::new(Leapfrog));
+    planet_list.set_view(Viewport::centered(Vector::ZERO, 100.0));
+
+    // a disk of dust grains on circular orbits about the pair, feeling the
+    // stars without pulling on them or each other
+    let mut rng = StdRng::seed_from_u64(3);
+    for _ in 0..3000 {
+        let r: Float = rng.gen_range(0.8..3.5);
+        let angle: Float = rng.gen_range(0.0..std::f64::consts::TAU as Float);
+        let pos = r * Vector::new(angle.cos(), angle.sin());
+        let vel = pos.perp().normalize() / r.sqrt();
+        planet_list.add_test_particle("Dust", pos, vel);
+    }
+
+    event_loop.run(move |event, _, control_flow| match event {
+        Event::MainEventsCleared => {
+            pixels.frame_mut().fill(0);
+            planet_list.update_and_render(&mut pixels);
+            pixels.render().unwrap();
+        }
+        Event::WindowEvent {
+            event: WindowEvent::CloseRequested,
+            ..
+        } => {
+            *control_flow = ControlFlow::Exit;
+        }
+        _ => {}
+    });
+}
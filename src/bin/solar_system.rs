@@ -5,8 +5,16 @@ use winit::{
     window::WindowBuilder,
 };
 
-use glam::f32::Vec2;
-use Wallfacer::{planet::*, system::*, util::*};
+use Wallfacer::{
+    integrator::Leapfrog,
+    physics::calc_init_orbital_velocity,
+    planet::*,
+    scalar::{Float, Vector},
+    system::*,
+    tides::{TidalDisruption, FLUID},
+    units::{UnitSystem, Viewport},
+    util::*,
+};
 
 fn main() {
     let event_loop = EventLoop::new();
@@ -20,30 +28,70 @@ fn main() {
     };
     let mut pixels = create_pixel_buffer(&window, WIDTH as u32, HEIGHT as u32);
 
-    let star = Planet::new(
-        "Star",
-        Vec2::new(300.0, 400.0),
-        10.0,
-        Vec2::new(0.0, 0.0),
-        10_000_000_000.1,
-        PlanetColor::white(),
-    );
-
-    let planet1 = Planet::create_satellite(&star, "Planet 1", 5.0, 1000.0, PlanetColor::blue());
-
-    let planet2 = Planet::create_satellite(&planet1, "Satellite", 4.0, 0.1, PlanetColor::green());
-    let planet3 = Planet::create_satellite(
-        &planet1,
-        "Tiny Satellite",
-        2.0,
-        10.0,
-        PlanetColor::new(150, 14, 21, 255),
+    // the Sun and the inner planets on circular orbits, in AU, solar masses and days
+    let units = UnitSystem::AstronomicalDays;
+    let gravity = units.gravity();
+    let mut sun = Planet::new(
+        "Sun",
+        Vector::ZERO,
+        0.00465,
+        Vector::ZERO,
+        1.0,
+        PlanetColor::new(255, 220, 120, 255),
     );
+    // name, semi-major axis, radius, mass, colour
+    let inner_planets: [(&str, Float, Float, Float, PlanetColor); 4] = [
+        (
+            "Mercury",
+            0.387,
+            1.63e-5,
+            1.66e-7,
+            PlanetColor::new(170, 170, 170, 255),
+        ),
+        (
+            "Venus",
+            0.723,
+            4.05e-5,
+            2.45e-6,
+            PlanetColor::new(230, 200, 140, 255),
+        ),
+        ("Earth", 1.0, 4.26e-5, 3.0e-6, PlanetColor::blue()),
+        ("Mars", 1.524, 2.27e-5, 3.23e-7, PlanetColor::red()),
+    ];
+    let mut planets = vec![];
+    for (name, distance, radius, mass, color) in inner_planets {
+        let mut planet = Planet::new(
+            name,
+            Vector::new(distance, 0.0),
+            radius,
+            Vector::ZERO,
+            mass,
+            color,
+        );
+        planet.vel = calc_init_orbital_velocity(&gravity, &planet, &sun);
+        planets.push(planet);
+    }
+    // the Sun recoils so the system as a whole stays put
+    let momentum: Vector = planets.iter().map(|p| p.mass * p.vel).sum();
+    sun.vel = -momentum / sun.mass;
+    planets.insert(0, sun);
 
-    let mut planet_list = PlanetSystem::from_vec(0.5, vec![star, planet1, planet2, planet3]);
-    for p in planet_list.list.iter() {
+    // half a day per frame
+    let mut planet_list = PlanetSystem::from_vec(0.5, planets);
+    planet_list.set_units(units);
+    planet_list.set_integrator(Box::new(Leapfrog));
+    // anything planet-sized straying too close to the Sun or a planet is torn apart
+    planet_list.set_tidal_disruption(Some(TidalDisruption {
+        coefficient: FLUID,
+        mass_ratio: 10.0,
+        min_mass: 1e-9,
+        fragments: 12,
+    }));
+    // Mars' orbit just fits in the window
+    planet_list.set_view(Viewport::centered(Vector::ZERO, 250.0));
+    for p in planet_list.planets() {
         println!(
-            "planet '{}' in position x: {}, y: {} ",
+            "planet '{}' in position x: {} AU, y: {} AU",
             p.name, p.pos.x, p.pos.y
         );
     }
@@ -53,13 +101,6 @@ fn main() {
             pixels.frame_mut().fill(0 as u8);
             planet_list.update_and_render(&mut pixels);
             _ = pixels.render().unwrap();
-            for p in planet_list.list.iter() {
-                print!(
-                    "planet '{}' in position x: {}, y: {} ",
-                    p.name, p.pos.x, p.pos.y
-                );
-            }
-            print!("\r");
         }
         Event::WindowEvent {
             event: WindowEvent::CloseRequested,
@@ -6,8 +6,9 @@ use winit::{
     window::WindowBuilder,
 };
 
-use glam::f32::Vec2;
-use Wallfacer::{planet::*, system::*, util::*};
+use Wallfacer::{
+    integrator::*, physics::GravityModel, planet::*, scalar::Vector, system::*, util::*,
+};
 
 fn main() {
     let event_loop = EventLoop::new();
@@ -25,16 +26,24 @@ fn main() {
 
     let sun = Planet::new(
         "Sun",
-        Vec2::new(500.0, 400.0),
+        Vector::new(500.0, 400.0),
         10.0,
-        Vec2::new(0.0, 0.0),
+        Vector::new(0.0, 0.0),
         10_000_000_000_000_0.0,
         PlanetColor::white(),
     );
 
-    let earth = Planet::create_satellite(&sun, "Earth", 8.0, 400.0, PlanetColor::green());
+    let gravity = GravityModel::default();
+    let earth = Planet::create_satellite(&gravity, &sun, "Earth", 8.0, 400.0, PlanetColor::green());
 
     let mut planet_list = PlanetSystem::from_vec(0.1, vec![sun, earth]);
+    // pick the integrator from the first argument, e.g. "cargo run --bin sun_and_earth -- rk4"
+    let scheme = std::env::args().nth(1).unwrap_or("verlet".to_string());
+    match integrator_from_name(&scheme) {
+        Some(integrator) => planet_list.set_integrator(integrator),
+        None => println!("Unknown integrator '{}', using the default", scheme),
+    }
+    println!("Integrator: {}", planet_list.integrator().name());
 
     event_loop.run(move |event, _, control_flow| match event {
         Event::MainEventsCleared => {
@@ -0,0 +1,127 @@
+use crate::planet::{Planet, PlanetColor};
+use crate::scalar::{Float, Vector};
+
+// Identifies a body for as long as it exists, unlike its index which shifts
+// whenever an earlier body is removed
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct BodyId(pub u64);
+
+// Structure-of-arrays store for the bodies of a system. Every quantity lives in
+// its own contiguous array, so integrators and force kernels stream through
+// positions, velocities and masses without dragging names and colours along.
+// Planet stays the way bodies are described and read back, one row at a time.
+#[derive(Clone, Default)]
+pub struct Bodies<'a> {
+    pub id: Vec<BodyId>,
+    pub pos: Vec<Vector>,
+    pub vel: Vec<Vector>,
+    pub accel: Vec<Vector>,
+    pub mass: Vec<Float>,
+    pub radius: Vec<Float>,
+    pub name: Vec<&'a str>,
+    pub color: Vec<PlanetColor>,
+    pub in_collision: Vec<bool>,
+    next_id: u64,
+}
+
+impl<'a> Bodies<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_planets(planets: impl IntoIterator<Item = Planet<'a>>) -> Self {
+        let mut bodies = Self::new();
+        for planet in planets {
+            bodies.push(planet);
+        }
+        bodies
+    }
+
+    pub fn len(&self) -> usize {
+        self.pos.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pos.is_empty()
+    }
+
+    pub fn push(&mut self, planet: Planet<'a>) -> BodyId {
+        let id = BodyId(self.next_id);
+        self.next_id += 1;
+        self.id.push(id);
+        self.pos.push(planet.pos);
+        self.vel.push(planet.vel);
+        self.accel.push(planet.accel);
+        self.mass.push(planet.mass);
+        self.radius.push(planet.radius);
+        self.name.push(planet.name);
+        self.color.push(planet.color);
+        self.in_collision.push(planet.in_collision);
+        id
+    }
+
+    pub fn index_of(&self, id: BodyId) -> Option<usize> {
+        self.id.iter().position(|&i| i == id)
+    }
+
+    // a copy of body i as a Planet
+    pub fn get(&self, i: usize) -> Planet<'a> {
+        Planet {
+            name: self.name[i],
+            pos: self.pos[i],
+            radius: self.radius[i],
+            vel: self.vel[i],
+            mass: self.mass[i],
+            color: self.color[i],
+            accel: self.accel[i],
+            in_collision: self.in_collision[i],
+        }
+    }
+
+    pub fn set(&mut self, i: usize, planet: Planet<'a>) {
+        self.pos[i] = planet.pos;
+        self.vel[i] = planet.vel;
+        self.accel[i] = planet.accel;
+        self.mass[i] = planet.mass;
+        self.radius[i] = planet.radius;
+        self.name[i] = planet.name;
+        self.color[i] = planet.color;
+        self.in_collision[i] = planet.in_collision;
+    }
+
+    // removes body i, shifting every later body down by one
+    pub fn remove(&mut self, i: usize) -> Planet<'a> {
+        let planet = self.get(i);
+        self.id.remove(i);
+        self.pos.remove(i);
+        self.vel.remove(i);
+        self.accel.remove(i);
+        self.mass.remove(i);
+        self.radius.remove(i);
+        self.name.remove(i);
+        self.color.remove(i);
+        self.in_collision.remove(i);
+        planet
+    }
+
+    // keeps the bodies whose entry in keep is true, in their original order
+    pub fn retain(&mut self, keep: &[bool]) {
+        fn filter<T>(items: &mut Vec<T>, keep: &[bool]) {
+            let mut k = keep.iter();
+            items.retain(|_| *k.next().unwrap());
+        }
+        filter(&mut self.id, keep);
+        filter(&mut self.pos, keep);
+        filter(&mut self.vel, keep);
+        filter(&mut self.accel, keep);
+        filter(&mut self.mass, keep);
+        filter(&mut self.radius, keep);
+        filter(&mut self.name, keep);
+        filter(&mut self.color, keep);
+        filter(&mut self.in_collision, keep);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Planet<'a>> + '_ {
+        (0..self.len()).map(|i| self.get(i))
+    }
+}
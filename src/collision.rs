@@ -0,0 +1,437 @@
+use std::collections::HashMap;
+
+use crate::bodies::{Bodies, BodyId};
+use crate::planet::Planet;
+use crate::scalar::{Float, Vector};
+
+// What happens to two bodies once they touch
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum CollisionResponse {
+    // they become one body
+    Merge,
+    // they bounce apart losing no kinetic energy
+    Elastic,
+    // they bounce apart with restitution times their closing speed, 0 leaves
+    // them resting against each other
+    Inelastic { restitution: Float },
+}
+
+impl CollisionResponse {
+    // the fraction of the closing speed the bodies separate with
+    pub fn restitution(&self) -> Option<Float> {
+        match self {
+            CollisionResponse::Merge => None,
+            CollisionResponse::Elastic => Some(1.0),
+            CollisionResponse::Inelastic { restitution } => Some(*restitution),
+        }
+    }
+}
+
+// The response for every pair of bodies, a default plus overrides for
+// particular pairs
+#[derive(Clone, PartialEq, Debug)]
+pub struct CollisionRules {
+    default: CollisionResponse,
+    pairs: HashMap<(BodyId, BodyId), CollisionResponse>,
+}
+
+impl CollisionRules {
+    pub fn new(default: CollisionResponse) -> Self {
+        Self {
+            default,
+            pairs: HashMap::new(),
+        }
+    }
+
+    pub fn set_default(&mut self, response: CollisionResponse) {
+        self.default = response;
+    }
+
+    pub fn default_response(&self) -> CollisionResponse {
+        self.default
+    }
+
+    pub fn set_pair(&mut self, a: BodyId, b: BodyId, response: CollisionResponse) {
+        self.pairs.insert(Self::key(a, b), response);
+    }
+
+    pub fn response(&self, a: BodyId, b: BodyId) -> CollisionResponse {
+        self.pairs
+            .get(&Self::key(a, b))
+            .copied()
+            .unwrap_or(self.default)
+    }
+
+    // drops the overrides naming bodies that no longer exist
+    pub fn forget(&mut self, gone: &[BodyId]) {
+        self.pairs
+            .retain(|(a, b), _| !gone.contains(a) && !gone.contains(b));
+    }
+
+    fn key(a: BodyId, b: BodyId) -> (BodyId, BodyId) {
+        if a.0 <= b.0 {
+            (a, b)
+        } else {
+            (b, a)
+        }
+    }
+}
+
+impl Default for CollisionRules {
+    fn default() -> Self {
+        Self::new(CollisionResponse::Merge)
+    }
+}
+
+// Impacts that would merge two bodies shatter them instead once the specific
+// impact energy, the kinetic energy of the collision in the centre of mass
+// frame per unit of total mass, exceeds threshold
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Fragmentation {
+    pub threshold: Float,
+    // how many equal fragments a collision leaves, at least 2
+    pub fragments: usize,
+    // the fraction of the collision's kinetic energy the fragments fly apart
+    // with, the rest is lost to heat
+    pub energy_retained: Float,
+}
+
+// One shattering: the parents are gone and the fragments have taken their
+// place
+#[derive(Clone, PartialEq, Debug)]
+pub struct FragmentEvent {
+    pub time: Float,
+    pub parents: Vec<BodyId>,
+    pub fragments: Vec<BodyId>,
+    pub specific_energy: Float,
+}
+
+// One merge: every absorbed body has become part of survivor, which is left
+// with the combined mass at the centre of mass moving with the total momentum
+#[derive(Clone, PartialEq, Debug)]
+pub struct MergeEvent {
+    pub time: Float,
+    pub survivor: BodyId,
+    pub absorbed: Vec<BodyId>,
+    pub mass: Float,
+    pub pos: Vector,
+    pub vel: Vector,
+    pub radius: Float,
+}
+
+// Every pair of bodies that touches at some point of a step taking them from
+// start to end in straight lines, as (i, j, t) with i < j in ascending order
+// and t the fraction of the step at which they first touch, 0 for pairs that
+// touch from the start. The broad phase sees each path as the circle around
+// it.
+pub fn swept_contacts(
+    start: &[Vector],
+    end: &[Vector],
+    radius: &[Float],
+) -> Vec<(usize, usize, Float)> {
+    let center: Vec<Vector> = start
+        .iter()
+        .zip(end)
+        .map(|(&a, &b)| 0.5 * (a + b))
+        .collect();
+    let reach: Vec<Float> = start
+        .iter()
+        .zip(end)
+        .zip(radius)
+        .map(|((&a, &b), &r)| r + 0.5 * a.distance(b))
+        .collect();
+    sweep_and_prune(&center, &reach)
+        .into_iter()
+        .filter_map(|(i, j)| {
+            time_of_impact(
+                start[j] - start[i],
+                (end[j] - start[j]) - (end[i] - start[i]),
+                radius[i] + radius[j],
+            )
+            .map(|t| (i, j, t))
+        })
+        .collect()
+}
+
+// The fraction of a step at which two circles whose centres are offset apart
+// at its start, and move by motion relative to each other during it, first
+// come within reach of each other. None if they never do during the step.
+pub fn time_of_impact(offset: Vector, motion: Vector, reach: Float) -> Option<Float> {
+    // |offset + motion t|^2 = reach^2
+    let c = offset.length_squared() - reach * reach;
+    if c <= 0.0 {
+        return Some(0.0);
+    }
+    let a = motion.length_squared();
+    let b = 2.0 * offset.dot(motion);
+    let discriminant = b * b - 4.0 * a * c;
+    if a == 0.0 || b >= 0.0 || discriminant < 0.0 {
+        return None;
+    }
+    let t = (-b - discriminant.sqrt()) / (2.0 * a);
+    (t <= 1.0).then_some(t)
+}
+
+// Broad phase: the pairs whose bounding boxes overlap, each as (i, j) with
+// i < j, in ascending order. Boxes are swept along x in order of their left
+// edges, keeping the ones still open at each new edge, so the cost is close
+// to O(n log n) unless many bodies share the same stretch of x.
+pub fn sweep_and_prune(pos: &[Vector], radius: &[Float]) -> Vec<(usize, usize)> {
+    let mut order: Vec<usize> = (0..pos.len()).collect();
+    order.sort_unstable_by(|&a, &b| (pos[a].x - radius[a]).total_cmp(&(pos[b].x - radius[b])));
+    let mut open: Vec<usize> = vec![];
+    let mut pairs = vec![];
+    for &i in order.iter() {
+        let left = pos[i].x - radius[i];
+        open.retain(|&j| pos[j].x + radius[j] >= left);
+        for &j in open.iter() {
+            if (pos[i].y - pos[j].y).abs() <= radius[i] + radius[j] {
+                pairs.push((i.min(j), i.max(j)));
+            }
+        }
+        open.push(i);
+    }
+    pairs.sort_unstable();
+    pairs
+}
+
+// Bodies linked by pairs, directly or through a chain of other pairs, as
+// groups of indices in ascending order. Bodies in no pair are left out.
+pub fn contact_groups(n: usize, pairs: &[(usize, usize)]) -> Vec<Vec<usize>> {
+    // union-find over the bodies, each root is the lowest index of its group
+    let mut parent: Vec<usize> = (0..n).collect();
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+    let mut touching = vec![false; n];
+    for &(i, j) in pairs {
+        touching[i] = true;
+        touching[j] = true;
+        let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+        parent[a.max(b)] = a.min(b);
+    }
+    let mut groups: Vec<Vec<usize>> = vec![];
+    let mut group_of = vec![usize::MAX; n];
+    for i in (0..n).filter(|&i| touching[i]) {
+        let r = root(&mut parent, i);
+        if group_of[r] == usize::MAX {
+            group_of[r] = groups.len();
+            groups.push(vec![]);
+        }
+        groups[group_of[r]].push(i);
+    }
+    groups
+}
+
+// Bounces bodies i and j off each other along the line between their centres
+// and pushes them apart until they just touch. Momentum and the centre of mass
+// are conserved; kinetic energy too when restitution is 1. Bodies already
+// moving apart keep their velocities. A test particle bounces off a massive
+// body as off a wall.
+pub fn bounce(bodies: &mut Bodies, i: usize, j: usize, restitution: Float) {
+    let (mi, mj) = (bodies.mass[i], bodies.mass[j]);
+    let offset = bodies.pos[j] - bodies.pos[i];
+    let distance = offset.length();
+    if mi + mj <= 0.0 || distance == 0.0 {
+        return;
+    }
+    let normal = offset / distance;
+    // how much of the push and of the change in velocity each body takes
+    let (share_i, share_j) = (mj / (mi + mj), mi / (mi + mj));
+    let overlap = bodies.radius[i] + bodies.radius[j] - distance;
+    if overlap > 0.0 {
+        bodies.pos[i] -= overlap * share_i * normal;
+        bodies.pos[j] += overlap * share_j * normal;
+    }
+    let closing = (bodies.vel[j] - bodies.vel[i]).dot(normal);
+    if closing < 0.0 {
+        let change = -(1.0 + restitution) * closing;
+        bodies.vel[i] -= change * share_i * normal;
+        bodies.vel[j] += change * share_j * normal;
+    }
+}
+
+// Merges every group into its most massive member, the earliest one on a tie.
+// Mass, momentum and the centre of mass are conserved. With combine_volume the
+// survivor's radius becomes that of a sphere holding the volume of the whole
+// group, otherwise it keeps its own. The absorbed bodies are removed
+// afterwards, so indices in groups all refer to the bodies as they were.
+pub fn merge_groups(
+    bodies: &mut Bodies,
+    groups: &[Vec<usize>],
+    combine_volume: bool,
+    time: Float,
+) -> Vec<MergeEvent> {
+    let mut keep = vec![true; bodies.len()];
+    let mut events = vec![];
+    for group in groups.iter().filter(|group| group.len() > 1) {
+        let survivor = group.iter().copied().fold(group[0], |best, i| {
+            if bodies.mass[i] > bodies.mass[best] {
+                i
+            } else {
+                best
+            }
+        });
+        let mut mass = 0.0;
+        let mut momentum = Vector::ZERO;
+        let mut weighted = Vector::ZERO;
+        let mut volume = 0.0;
+        for &i in group {
+            mass += bodies.mass[i];
+            momentum += bodies.mass[i] * bodies.vel[i];
+            weighted += bodies.mass[i] * bodies.pos[i];
+            volume += bodies.radius[i].powi(3);
+        }
+        let pos = if mass > 0.0 {
+            weighted / mass
+        } else {
+            bodies.pos[survivor]
+        };
+        let vel = if mass > 0.0 {
+            momentum / mass
+        } else {
+            bodies.vel[survivor]
+        };
+        let radius = if combine_volume {
+            volume.cbrt()
+        } else {
+            bodies.radius[survivor]
+        };
+        bodies.mass[survivor] = mass;
+        bodies.pos[survivor] = pos;
+        bodies.vel[survivor] = vel;
+        bodies.radius[survivor] = radius;
+        let mut absorbed = vec![];
+        for &i in group.iter().filter(|&&i| i != survivor) {
+            keep[i] = false;
+            absorbed.push(bodies.id[i]);
+        }
+        events.push(MergeEvent {
+            time,
+            survivor: bodies.id[survivor],
+            absorbed,
+            mass,
+            pos,
+            vel,
+            radius,
+        });
+    }
+    if !events.is_empty() {
+        bodies.retain(&keep);
+    }
+    events
+}
+
+// Kinetic energy of the collision of bodies i and j in their centre of mass
+// frame, per unit of their total mass
+pub fn specific_impact_energy(bodies: &Bodies, i: usize, j: usize) -> Float {
+    let total = bodies.mass[i] + bodies.mass[j];
+    if total <= 0.0 {
+        return 0.0;
+    }
+    let reduced = bodies.mass[i] * bodies.mass[j] / total;
+    0.5 * reduced * (bodies.vel[i] - bodies.vel[j]).length_squared() / total
+}
+
+// Adds count equal fragments of whole on a ring about its position, the first
+// at angle start, spaced so neighbouring fragments do not touch. They share
+// its mass and volume, take its name and colour, and move with its velocity
+// plus expansion away from the centre. Removing what broke up is up to the
+// caller.
+pub fn fragment_ring<'a>(
+    bodies: &mut Bodies<'a>,
+    whole: &Planet<'a>,
+    count: usize,
+    start: Float,
+    expansion: Float,
+) -> Vec<BodyId> {
+    let pi = std::f64::consts::PI as Float;
+    let radius = whole.radius / (count as Float).cbrt();
+    let ring = 1.05 * radius / (pi / count as Float).sin();
+    let mut fragments = vec![];
+    for k in 0..count {
+        let angle = start + 2.0 * pi * k as Float / count as Float;
+        let direction = Vector::new(angle.cos(), angle.sin());
+        fragments.push(bodies.push(Planet::new(
+            whole.name,
+            whole.pos + ring * direction,
+            radius,
+            whole.vel + expansion * direction,
+            whole.mass / count as Float,
+            whole.color,
+        )));
+    }
+    fragments
+}
+
+// Replaces every group of bodies with equal fragments on a ring about its
+// centre of mass, flying straight out from it. Mass, momentum, volume and the
+// centre of mass are conserved. The fragments take the name and colour of the
+// most massive parent and are added after every other body.
+pub fn shatter_groups(
+    bodies: &mut Bodies,
+    groups: &[Vec<usize>],
+    fragmentation: &Fragmentation,
+    time: Float,
+) -> Vec<FragmentEvent> {
+    let count = fragmentation.fragments.max(2);
+    let mut keep = vec![true; bodies.len()];
+    let mut events = vec![];
+    for group in groups.iter().filter(|group| group.len() > 1) {
+        let mut mass = 0.0;
+        let mut momentum = Vector::ZERO;
+        let mut weighted = Vector::ZERO;
+        let mut volume = 0.0;
+        let mut largest = group[0];
+        for &i in group {
+            mass += bodies.mass[i];
+            momentum += bodies.mass[i] * bodies.vel[i];
+            weighted += bodies.mass[i] * bodies.pos[i];
+            volume += bodies.radius[i].powi(3);
+            if bodies.mass[i] > bodies.mass[largest] {
+                largest = i;
+            }
+        }
+        if mass <= 0.0 {
+            continue;
+        }
+        let center = weighted / mass;
+        let vel = momentum / mass;
+        let mut kinetic = 0.0;
+        for &i in group {
+            kinetic += 0.5 * bodies.mass[i] * (bodies.vel[i] - vel).length_squared();
+        }
+        let expansion = (2.0 * fragmentation.energy_retained * kinetic / mass).sqrt();
+        let offset = bodies.pos[largest] - center;
+        let whole = Planet::new(
+            bodies.name[largest],
+            center,
+            volume.cbrt(),
+            vel,
+            mass,
+            bodies.color[largest],
+        );
+
+        let mut parents = vec![];
+        for &i in group {
+            keep[i] = false;
+            parents.push(bodies.id[i]);
+        }
+        let fragments = fragment_ring(bodies, &whole, count, offset.y.atan2(offset.x), expansion);
+        keep.resize(bodies.len(), true);
+        events.push(FragmentEvent {
+            time,
+            parents,
+            fragments,
+            specific_energy: kinetic / mass,
+        });
+    }
+    if !events.is_empty() {
+        bodies.retain(&keep);
+    }
+    events
+}
@@ -0,0 +1,104 @@
+use crate::parallel::map_bodies;
+use crate::physics::GravityModel;
+use crate::scalar::{Float, Vector};
+
+// Conserved quantities of the whole system at one instant. Angular momentum is
+// the z component about the origin, the only one a planar system has.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Diagnostics {
+    pub kinetic: Float,
+    pub potential: Float,
+    pub momentum: Vector,
+    pub angular_momentum: Float,
+    pub center_of_mass: Vector,
+    pub mass: Float,
+    // sums of the magnitudes of every body's contribution, what drifts are
+    // measured against when a total happens to be zero
+    momentum_scale: Float,
+    angular_momentum_scale: Float,
+}
+
+// How far the conserved quantities have moved from an earlier measurement,
+// each relative to the size of the quantity
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Drift {
+    pub energy: Float,
+    pub momentum: Float,
+    pub angular_momentum: Float,
+}
+
+impl Diagnostics {
+    pub fn measure(
+        gravity: &GravityModel,
+        pos: &[Vector],
+        vel: &[Vector],
+        mass: &[Float],
+    ) -> Diagnostics {
+        let mut diagnostics = Diagnostics {
+            kinetic: 0.0,
+            potential: 0.0,
+            momentum: Vector::ZERO,
+            angular_momentum: 0.0,
+            center_of_mass: Vector::ZERO,
+            mass: 0.0,
+            momentum_scale: 0.0,
+            angular_momentum_scale: 0.0,
+        };
+        let mut weighted = Vector::ZERO;
+        for ((&p, &v), &m) in pos.iter().zip(vel).zip(mass) {
+            diagnostics.kinetic += 0.5 * m * v.length_squared();
+            diagnostics.momentum += m * v;
+            diagnostics.momentum_scale += m * v.length();
+            diagnostics.angular_momentum += m * p.perp_dot(v);
+            diagnostics.angular_momentum_scale += (m * p.perp_dot(v)).abs();
+            diagnostics.mass += m;
+            weighted += m * p;
+        }
+        if diagnostics.mass > 0.0 {
+            diagnostics.center_of_mass = weighted / diagnostics.mass;
+        }
+        // every pair of massive bodies once, O(n^2), test particles add nothing
+        let massive: Vec<usize> = (0..mass.len()).filter(|&i| mass[i] != 0.0).collect();
+        diagnostics.potential = map_bodies(massive.len(), |a| {
+            let i = massive[a];
+            let mut potential = 0.0;
+            for &j in &massive[a + 1..] {
+                potential += mass[i] * gravity.potential(pos[i].distance_squared(pos[j]), mass[j]);
+            }
+            potential
+        })
+        .iter()
+        .sum();
+        diagnostics
+    }
+
+    pub fn energy(&self) -> Float {
+        self.kinetic + self.potential
+    }
+
+    pub fn drift(&self, initial: &Diagnostics) -> Drift {
+        let relative = |change: Float, size: Float| {
+            if size > 0.0 {
+                change.abs() / size
+            } else {
+                change.abs()
+            }
+        };
+        let energy_size = if initial.energy() != 0.0 {
+            initial.energy().abs()
+        } else {
+            initial.kinetic + initial.potential.abs()
+        };
+        Drift {
+            energy: relative(self.energy() - initial.energy(), energy_size),
+            momentum: relative(
+                (self.momentum - initial.momentum).length(),
+                initial.momentum_scale,
+            ),
+            angular_momentum: relative(
+                self.angular_momentum - initial.angular_momentum,
+                initial.angular_momentum_scale,
+            ),
+        }
+    }
+}
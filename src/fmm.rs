@@ -0,0 +1,333 @@
+use crate::parallel::map_bodies;
+use crate::physics::{calc_point_accel, ForceSolver, GravityModel};
+use crate::quadtree::QuadTree;
+use crate::scalar::{Float, Vector};
+
+// Fast multipole method for the 1/r potential of bodies in a plane.
+//
+// The potential is the three dimensional one restricted to z = 0, which is not
+// harmonic in two dimensions, so the usual complex (log kernel) expansions do
+// not apply. Cells carry Cartesian Taylor expansions up to total order `order`
+// instead, and a dual tree walk (Dehnen 2002) turns well separated cell pairs
+// into local expansions, giving O(n) work for a fixed accuracy.
+//
+// Softening only enters the direct sums between neighbouring leaves, the
+// expansions are of the plain 1/r potential. That is exact for the spline
+// kernel once cells are further apart than its radius, and Plummer softening
+// differs from 1/r by a relative epsilon^2 / r^2 out there.
+pub struct Fmm {
+    // highest total order of the expansions, higher is more accurate
+    pub order: usize,
+    // cells interact through their expansions once (r_a + r_b) < theta * distance
+    pub theta: Float,
+    // cells with at most this many bodies are summed directly
+    pub leaf_size: usize,
+}
+
+impl Fmm {
+    pub fn new(order: usize) -> Fmm {
+        Fmm {
+            order,
+            theta: 0.5,
+            leaf_size: 16,
+        }
+    }
+}
+
+impl ForceSolver for Fmm {
+    fn accelerations(&self, gravity: &GravityModel, pos: &[Vector], mass: &[Float]) -> Vec<Vector> {
+        let tree = QuadTree::with_leaf_size(pos, mass, self.leaf_size);
+        let mut walk = Walk::new(&tree, gravity, pos, mass, self.order, self.theta);
+        walk.upward(0);
+        walk.interact(0, 0);
+        walk.downward(0);
+        walk.evaluate()
+    }
+
+    fn name(&self) -> &'static str {
+        "Fast multipole"
+    }
+}
+
+// the multi-indices (a, b) with a + b <= order, graded by total order
+struct Indices {
+    list: Vec<(usize, usize)>,
+    order: usize,
+}
+
+impl Indices {
+    fn new(order: usize) -> Indices {
+        let list = (0..=order)
+            .flat_map(|k| (0..=k).map(move |b| (k - b, b)))
+            .collect();
+        Indices { list, order }
+    }
+
+    fn index(&self, a: usize, b: usize) -> usize {
+        let k = a + b;
+        k * (k + 1) / 2 + b
+    }
+
+    fn len(&self) -> usize {
+        self.list.len()
+    }
+}
+
+struct Walk<'t> {
+    tree: &'t QuadTree,
+    gravity: &'t GravityModel,
+    pos: &'t [Vector],
+    mass: &'t [Float],
+    theta: Float,
+    indices: Indices,
+    binomial: Vec<Vec<Float>>,
+    // per cell: expansion centre, radius enclosing its bodies, multipole and local coefficients
+    center: Vec<Vector>,
+    radius: Vec<Float>,
+    multipole: Vec<Vec<Float>>,
+    local: Vec<Vec<Float>>,
+    // per leaf, the leaves whose bodies its own feel one by one, in walk order
+    near: Vec<Vec<usize>>,
+}
+
+impl<'t> Walk<'t> {
+    fn new(
+        tree: &'t QuadTree,
+        gravity: &'t GravityModel,
+        pos: &'t [Vector],
+        mass: &'t [Float],
+        order: usize,
+        theta: Float,
+    ) -> Walk<'t> {
+        let indices = Indices::new(order);
+        let mut binomial = vec![vec![0.0; order + 1]; order + 1];
+        for n in 0..=order {
+            binomial[n][0] = 1.0;
+            for k in 1..=n {
+                binomial[n][k] =
+                    binomial[n - 1][k - 1] + if k < n { binomial[n - 1][k] } else { 0.0 };
+            }
+        }
+        let cells = tree.nodes.len();
+        let terms = indices.len();
+        Walk {
+            tree,
+            gravity,
+            pos,
+            mass,
+            theta,
+            indices,
+            binomial,
+            center: tree.nodes.iter().map(|n| n.com).collect(),
+            radius: vec![0.0; cells],
+            multipole: vec![vec![0.0; terms]; cells],
+            local: vec![vec![0.0; terms]; cells],
+            near: vec![vec![]; cells],
+        }
+    }
+
+    fn children(&self, cell: usize) -> Option<std::ops::Range<usize>> {
+        self.tree.nodes[cell].first_child.map(|c| c..c + 4)
+    }
+
+    fn bodies(&self, cell: usize) -> &'t [usize] {
+        let node = &self.tree.nodes[cell];
+        &self.tree.order[node.start..node.end]
+    }
+
+    // multipoles about each cell's centre of mass: M[a, b] = sum m (c - y)^(a, b)
+    fn upward(&mut self, cell: usize) {
+        let c = self.center[cell];
+        match self.children(cell) {
+            None => {
+                for &j in self.bodies(cell) {
+                    let d = c - self.pos[j];
+                    self.radius[cell] = self.radius[cell].max(d.length());
+                    for (t, &(a, b)) in self.indices.list.iter().enumerate() {
+                        self.multipole[cell][t] +=
+                            self.mass[j] * d.x.powi(a as i32) * d.y.powi(b as i32);
+                    }
+                }
+            }
+            Some(children) => {
+                for child in children {
+                    if self.tree.nodes[child].start == self.tree.nodes[child].end {
+                        continue;
+                    }
+                    self.upward(child);
+                    // shift the child's multipole by delta = c - c_child
+                    let delta = c - self.center[child];
+                    self.radius[cell] = self.radius[cell].max(delta.length() + self.radius[child]);
+                    for (t, &(a, b)) in self.indices.list.iter().enumerate() {
+                        let mut sum = 0.0;
+                        for i in 0..=a {
+                            for j in 0..=b {
+                                sum += self.binomial[a][i]
+                                    * self.binomial[b][j]
+                                    * delta.x.powi((a - i) as i32)
+                                    * delta.y.powi((b - j) as i32)
+                                    * self.multipole[child][self.indices.index(i, j)];
+                            }
+                        }
+                        self.multipole[cell][t] += sum;
+                    }
+                }
+            }
+        }
+    }
+
+    fn interact(&mut self, a: usize, b: usize) {
+        let (node_a, node_b) = (&self.tree.nodes[a], &self.tree.nodes[b]);
+        if node_a.start == node_a.end || node_b.start == node_b.end || node_b.mass == 0.0 {
+            return;
+        }
+        if a == b {
+            match self.children(a) {
+                None => self.direct(a, b),
+                Some(children) => {
+                    for i in children.clone() {
+                        for j in children.clone() {
+                            self.interact(i, j);
+                        }
+                    }
+                }
+            }
+            return;
+        }
+
+        let dist = self.center[a].distance(self.center[b]);
+        if self.radius[a] + self.radius[b] < self.theta * dist {
+            self.multipole_to_local(a, b);
+            return;
+        }
+        match (self.children(a), self.children(b)) {
+            (None, None) => self.direct(a, b),
+            (Some(children), None) => children.for_each(|i| self.interact(i, b)),
+            (None, Some(children)) => children.for_each(|j| self.interact(a, j)),
+            (Some(children_a), Some(children_b)) => {
+                // open the bigger cell
+                if self.radius[a] >= self.radius[b] {
+                    children_a.for_each(|i| self.interact(i, b));
+                } else {
+                    children_b.for_each(|j| self.interact(a, j));
+                }
+            }
+        }
+    }
+
+    fn direct(&mut self, a: usize, b: usize) {
+        self.near[a].push(b);
+    }
+
+    // L[alpha] += sum over beta of binomial(alpha + beta, alpha) * T[alpha + beta] * M[beta],
+    // where T are the Taylor coefficients of 1/|r| at r = c_a - c_b
+    fn multipole_to_local(&mut self, a: usize, b: usize) {
+        let taylor = self.taylor_coefficients(self.center[a] - self.center[b]);
+        let order = self.indices.order;
+        for (s, &(a1, a2)) in self.indices.list.iter().enumerate() {
+            let mut sum = 0.0;
+            for (t, &(b1, b2)) in self.indices.list.iter().enumerate() {
+                if a1 + a2 + b1 + b2 > order {
+                    break;
+                }
+                sum += self.binomial[a1 + b1][a1]
+                    * self.binomial[a2 + b2][a2]
+                    * taylor[self.indices.index(a1 + b1, a2 + b2)]
+                    * self.multipole[b][t];
+            }
+            self.local[a][s] += sum;
+        }
+    }
+
+    // T[n] = D^n (1/|r|) / n!, from the recurrence
+    // |n| r^2 T[n] + (2|n| - 1) sum_i r_i T[n - e_i] + (|n| - 1) sum_i T[n - 2 e_i] = 0
+    fn taylor_coefficients(&self, r: Vector) -> Vec<Float> {
+        let r2 = r.length_squared();
+        let mut taylor = vec![0.0; self.indices.len()];
+        taylor[0] = 1.0 / r2.sqrt();
+        for (t, &(a, b)) in self.indices.list.iter().enumerate().skip(1) {
+            let k = (a + b) as Float;
+            let get = |i: usize, j: usize| taylor[self.indices.index(i, j)];
+            let mut sum = 0.0;
+            if a >= 1 {
+                sum += (2.0 * k - 1.0) * r.x * get(a - 1, b);
+            }
+            if b >= 1 {
+                sum += (2.0 * k - 1.0) * r.y * get(a, b - 1);
+            }
+            if a >= 2 {
+                sum += (k - 1.0) * get(a - 2, b);
+            }
+            if b >= 2 {
+                sum += (k - 1.0) * get(a, b - 2);
+            }
+            taylor[t] = -sum / (k * r2);
+        }
+        taylor
+    }
+
+    // shifts every local expansion down to the leaves
+    fn downward(&mut self, cell: usize) {
+        let Some(children) = self.children(cell) else {
+            return;
+        };
+        for child in children {
+            if self.tree.nodes[child].start == self.tree.nodes[child].end {
+                continue;
+            }
+            // shift the local expansion by eps = c_child - c
+            let eps = self.center[child] - self.center[cell];
+            for (t, &(g1, g2)) in self.indices.list.iter().enumerate() {
+                let mut sum = 0.0;
+                for (s, &(a1, a2)) in self.indices.list.iter().enumerate() {
+                    if a1 < g1 || a2 < g2 {
+                        continue;
+                    }
+                    sum += self.binomial[a1][g1]
+                        * self.binomial[a2][g2]
+                        * eps.x.powi((a1 - g1) as i32)
+                        * eps.y.powi((a2 - g2) as i32)
+                        * self.local[cell][s];
+                }
+                self.local[child][t] += sum;
+            }
+            self.downward(child);
+        }
+    }
+
+    // the near field body by body plus the local expansion of its leaf. Each
+    // body only reads the tree, so they are spread across threads.
+    fn evaluate(&self) -> Vec<Vector> {
+        let mut leaf = vec![0; self.pos.len()];
+        for cell in (0..self.tree.nodes.len()).filter(|&c| self.children(c).is_none()) {
+            for &i in self.bodies(cell) {
+                leaf[i] = cell;
+            }
+        }
+        map_bodies(self.pos.len(), |i| {
+            let cell = leaf[i];
+            let mut accel = Vector::ZERO;
+            for &b in &self.near[cell] {
+                for &j in self.bodies(b) {
+                    if i != j {
+                        accel +=
+                            calc_point_accel(self.gravity, self.pos[i], self.pos[j], self.mass[j]);
+                    }
+                }
+            }
+            // acceleration is G times the gradient of sum L[alpha] e^alpha
+            let e = self.pos[i] - self.center[cell];
+            let mut grad = Vector::ZERO;
+            for (t, &(a, b)) in self.indices.list.iter().enumerate() {
+                let l = self.local[cell][t];
+                if a >= 1 {
+                    grad.x += l * a as Float * e.x.powi(a as i32 - 1) * e.y.powi(b as i32);
+                }
+                if b >= 1 {
+                    grad.y += l * b as Float * e.x.powi(a as i32) * e.y.powi(b as i32 - 1);
+                }
+            }
+            accel + self.gravity.g * grad
+        })
+    }
+}
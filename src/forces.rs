@@ -0,0 +1,135 @@
+use crate::bodies::BodyId;
+use crate::scalar::{Float, Vector};
+
+// The bodies as a force sees them, at whatever point of a step the integrator
+// asks for accelerations
+pub struct BodyState<'s> {
+    pub id: &'s [BodyId],
+    pub pos: &'s [Vector],
+    pub vel: &'s [Vector],
+    pub mass: &'s [Float],
+    pub radius: &'s [Float],
+}
+
+impl BodyState<'_> {
+    pub fn len(&self) -> usize {
+        self.pos.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pos.is_empty()
+    }
+}
+
+// Anything besides the gravity of the bodies that accelerates them. A system
+// adds the accelerations of every force it holds to those from gravity.
+pub trait Force {
+    // adds the acceleration this force gives every body to accel
+    fn add_accelerations(&self, bodies: &BodyState, accel: &mut [Vector]);
+
+    fn name(&self) -> &'static str;
+}
+
+// Gravity of something far bigger than the system, like the ground under a
+// projectile, the same everywhere
+pub struct UniformField {
+    pub accel: Vector,
+}
+
+impl Force for UniformField {
+    fn add_accelerations(&self, bodies: &BodyState, accel: &mut [Vector]) {
+        for a in accel.iter_mut().take(bodies.len()) {
+            *a += self.accel;
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "Uniform field"
+    }
+}
+
+// Quadratic drag through a gas of the given density moving with wind:
+// a = -rho Cd A |u| u / 2m, with u the velocity relative to the gas and A the
+// cross-section of the body. Massless bodies feel none.
+pub struct Drag {
+    pub density: Float,
+    pub coefficient: Float,
+    pub wind: Vector,
+}
+
+impl Force for Drag {
+    fn add_accelerations(&self, bodies: &BodyState, accel: &mut [Vector]) {
+        let pi = std::f64::consts::PI as Float;
+        for (i, a) in accel.iter_mut().enumerate().take(bodies.len()) {
+            if bodies.mass[i] <= 0.0 {
+                continue;
+            }
+            let u = bodies.vel[i] - self.wind;
+            let area = pi * bodies.radius[i] * bodies.radius[i];
+            *a -= 0.5 * self.density * self.coefficient * area * u.length() * u / bodies.mass[i];
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "Drag"
+    }
+}
+
+// Pressure of the light of one body, the star, on all the others:
+// a = L Q A / (4 pi d^2 c m) away from the star, with A the cross-section of
+// the body and Q how well it absorbs or scatters, 1 for a perfect absorber.
+// Nothing happens once the star is gone.
+pub struct RadiationPressure {
+    pub star: BodyId,
+    pub luminosity: Float,
+    pub speed_of_light: Float,
+    pub efficiency: Float,
+}
+
+impl Force for RadiationPressure {
+    fn add_accelerations(&self, bodies: &BodyState, accel: &mut [Vector]) {
+        let Some(star) = bodies.id.iter().position(|&id| id == self.star) else {
+            return;
+        };
+        for (i, a) in accel.iter_mut().enumerate().take(bodies.len()) {
+            let away = bodies.pos[i] - bodies.pos[star];
+            let d2 = away.length_squared();
+            if i == star || bodies.mass[i] <= 0.0 || d2 == 0.0 {
+                continue;
+            }
+            // pi r^2 / (4 pi) per unit distance squared
+            let flux = self.luminosity * bodies.radius[i] * bodies.radius[i]
+                / (4.0 * d2 * self.speed_of_light);
+            *a += self.efficiency * flux / bodies.mass[i] * away / d2.sqrt();
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "Radiation pressure"
+    }
+}
+
+// Any acceleration given as a closure of the bodies and the index of the body
+// it acts on, for trying out new physics
+pub struct CustomForce<F: Fn(&BodyState, usize) -> Vector> {
+    name: &'static str,
+    accel: F,
+}
+
+impl<F: Fn(&BodyState, usize) -> Vector> CustomForce<F> {
+    pub fn new(name: &'static str, accel: F) -> Self {
+        Self { name, accel }
+    }
+}
+
+impl<F: Fn(&BodyState, usize) -> Vector> Force for CustomForce<F> {
+    fn add_accelerations(&self, bodies: &BodyState, accel: &mut [Vector]) {
+        for (i, a) in accel.iter_mut().enumerate().take(bodies.len()) {
+            *a += (self.accel)(bodies, i);
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+}
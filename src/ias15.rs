@@ -0,0 +1,365 @@
+use crate::integrator::{AccelFn, Integrator};
+use crate::scalar::{Float, Vector};
+
+// Gauss-Radau spacings on [0, 1], the first node is the start of the step
+const H: [f64; 8] = [
+    0.0,
+    0.056_262_560_536_922_15,
+    0.180_240_691_736_892_36,
+    0.352_624_717_113_169_6,
+    0.547_153_626_330_555_4,
+    0.734_210_177_215_410_5,
+    0.885_320_946_839_095_8,
+    0.977_520_613_561_287_5,
+];
+
+// a step is redone when the controller wants to shrink it below this fraction,
+// and a step never grows by more than its inverse
+const SAFETY_FACTOR: Float = 0.25;
+const MAX_ITERATIONS: usize = 12;
+
+// Single precision cannot follow the series far enough for a tighter epsilon
+// to buy any accuracy
+#[cfg(not(feature = "single-precision"))]
+const DEFAULT_EPSILON: Float = 1e-9;
+#[cfg(feature = "single-precision")]
+const DEFAULT_EPSILON: Float = 1e-2;
+
+// IAS15 (Rein & Spiegel 2015): a 15th order implicit Gauss-Radau integrator
+// with its own step size control, the timescale criterion of Pham, Rein &
+// Spiegel (2024). Every call to step covers the whole timestep with as many
+// internal substeps as epsilon requires.
+pub struct Ias15 {
+    // substeps are (7! epsilon)^(1/7) times the shortest timescale of any
+    // body, sqrt(2 |a|^2 / (|jerk|^2 + |a| |snap|)), so the error of each is
+    // about epsilon relative to its acceleration
+    pub epsilon: Float,
+    // substeps are never shorter than this, even if the error estimate asks for it
+    pub min_dt: Float,
+    dt_try: Float,
+    dt_last_done: Float,
+    // conversion between the divided differences g and the series coefficients b:
+    // b[k] = sum over j >= k of c[k][j] * g[j], and g[j] = sum over k >= j of d[j][k] * b[k]
+    c: [[Float; 7]; 7],
+    d: [[Float; 7]; 7],
+    b: [Vec<Vector>; 7],
+    e: [Vec<Vector>; 7],
+    br: [Vec<Vector>; 7],
+    er: [Vec<Vector>; 7],
+    // compensated summation terms for the position and velocity updates
+    csx: Vec<Vector>,
+    csv: Vec<Vector>,
+}
+
+impl Ias15 {
+    pub fn new() -> Ias15 {
+        Self::with_epsilon(DEFAULT_EPSILON)
+    }
+
+    pub fn with_epsilon(epsilon: Float) -> Ias15 {
+        let (c, d) = conversion_coefficients();
+        Ias15 {
+            epsilon,
+            min_dt: 0.0,
+            dt_try: 0.0,
+            dt_last_done: 0.0,
+            c,
+            d,
+            b: Default::default(),
+            e: Default::default(),
+            br: Default::default(),
+            er: Default::default(),
+            csx: vec![],
+            csv: vec![],
+        }
+    }
+
+    // the step size the controller will try next, zero before the first step
+    pub fn next_timestep(&self) -> Float {
+        self.dt_try
+    }
+
+    fn reset(&mut self, n: usize) {
+        for k in 0..7 {
+            self.b[k] = vec![Vector::ZERO; n];
+            self.e[k] = vec![Vector::ZERO; n];
+            self.br[k] = vec![Vector::ZERO; n];
+            self.er[k] = vec![Vector::ZERO; n];
+        }
+        self.csx = vec![Vector::ZERO; n];
+        self.csv = vec![Vector::ZERO; n];
+        self.dt_last_done = 0.0;
+    }
+
+    // a single attempt at a step of length dt. Returns the step size the
+    // controller suggests next, or Err with a smaller one if the step was rejected
+    // and pos/vel were left untouched. A forced step is never rejected.
+    fn substep(
+        &mut self,
+        pos: &mut [Vector],
+        vel: &mut [Vector],
+        dt: Float,
+        accel: &AccelFn,
+        force: bool,
+    ) -> Result<Float, Float> {
+        let n = pos.len();
+        let a0 = accel(pos, vel);
+        let mut g: [Vec<Vector>; 7] = Default::default();
+        for (j, g_j) in g.iter_mut().enumerate() {
+            *g_j = (0..n)
+                .map(|i| (j..7).map(|k| self.d[j][k] * self.b[k][i]).sum())
+                .collect();
+        }
+
+        let mut x = pos.to_vec();
+        let mut v = vel.to_vec();
+        let mut previous_error = Float::INFINITY;
+        for iteration in 0..MAX_ITERATIONS {
+            let mut corrector_error = 0.0;
+            for s in 1..8 {
+                let h = H[s] as Float;
+                for i in 0..n {
+                    let b = |k: usize| self.b[k][i];
+                    x[i] = pos[i]
+                        + dt * h * vel[i]
+                        + dt * dt
+                            * h
+                            * h
+                            * (a0[i] / 2.0
+                                + h * (b(0) / 6.0
+                                    + h * (b(1) / 12.0
+                                        + h * (b(2) / 20.0
+                                            + h * (b(3) / 30.0
+                                                + h * (b(4) / 42.0
+                                                    + h * (b(5) / 56.0 + h * b(6) / 72.0)))))));
+                    v[i] = vel[i]
+                        + dt * h
+                            * (a0[i]
+                                + h * (b(0) / 2.0
+                                    + h * (b(1) / 3.0
+                                        + h * (b(2) / 4.0
+                                            + h * (b(3) / 5.0
+                                                + h * (b(4) / 6.0
+                                                    + h * (b(5) / 7.0 + h * b(6) / 8.0)))))));
+                }
+                let a = accel(&x, &v);
+                let mut max_change: Float = 0.0;
+                let mut max_a: Float = 0.0;
+                for i in 0..n {
+                    // newest divided difference at node s
+                    let mut y = (a[i] - a0[i]) / h;
+                    for j in 0..s - 1 {
+                        y = (y - g[j][i]) / (h - H[j + 1] as Float);
+                    }
+                    let change = y - g[s - 1][i];
+                    g[s - 1][i] = y;
+                    for k in 0..s {
+                        self.b[k][i] += self.c[k][s - 1] * change;
+                    }
+                    max_change = max_change.max(change.abs().max_element());
+                    max_a = max_a.max(a[i].abs().max_element());
+                }
+                if s == 7 {
+                    corrector_error = if max_a > 0.0 { max_change / max_a } else { 0.0 };
+                }
+            }
+            // stop once converged, or once rounding keeps the error from shrinking
+            if corrector_error < Float::EPSILON
+                || (iteration > 2 && corrector_error >= previous_error)
+            {
+                break;
+            }
+            previous_error = corrector_error;
+        }
+
+        // the shortest timescale of any body, from its acceleration, jerk and
+        // snap at the end of the step in units of dt. These low derivatives stay
+        // well above rounding noise, where b6 / a bottoms out near 1e-10 in close
+        // encounters and a controller chasing it shrinks the step forever.
+        let mut min_timescale2 = Float::INFINITY;
+        for (i, &start) in a0.iter().enumerate() {
+            let b = |k: usize| self.b[k][i];
+            let a = start + b(0) + b(1) + b(2) + b(3) + b(4) + b(5) + b(6);
+            let jerk =
+                b(0) + 2.0 * b(1) + 3.0 * b(2) + 4.0 * b(3) + 5.0 * b(4) + 6.0 * b(5) + 7.0 * b(6);
+            let snap =
+                2.0 * b(1) + 6.0 * b(2) + 12.0 * b(3) + 20.0 * b(4) + 30.0 * b(5) + 42.0 * b(6);
+            let a2 = a.length_squared();
+            let timescale2 =
+                2.0 * a2 / (jerk.length_squared() + (snap.length_squared() * a2).sqrt());
+            if timescale2.is_normal() {
+                min_timescale2 = min_timescale2.min(timescale2);
+            }
+        }
+        let mut dt_new = if min_timescale2.is_normal() {
+            dt * min_timescale2.sqrt() * (self.epsilon * 5040.0).powf(1.0 / 7.0)
+        } else {
+            dt / SAFETY_FACTOR
+        };
+        if !force && dt_new.abs() < SAFETY_FACTOR * dt.abs() {
+            if self.dt_last_done != 0.0 {
+                self.predict_next_step(dt_new / self.dt_last_done, true);
+            }
+            return Err(dt_new);
+        }
+        if dt_new.abs() > dt.abs() / SAFETY_FACTOR {
+            dt_new = dt / SAFETY_FACTOR;
+        }
+
+        for i in 0..n {
+            let b = |k: usize| self.b[k][i];
+            let dx = (b(6) / 72.0
+                + b(5) / 56.0
+                + b(4) / 42.0
+                + b(3) / 30.0
+                + b(2) / 20.0
+                + b(1) / 12.0
+                + b(0) / 6.0
+                + a0[i] / 2.0)
+                * dt
+                * dt
+                + vel[i] * dt;
+            let dv = (b(6) / 8.0
+                + b(5) / 7.0
+                + b(4) / 6.0
+                + b(3) / 5.0
+                + b(2) / 4.0
+                + b(1) / 3.0
+                + b(0) / 2.0
+                + a0[i])
+                * dt;
+            add_compensated(&mut pos[i], &mut self.csx[i], dx);
+            add_compensated(&mut vel[i], &mut self.csv[i], dv);
+        }
+
+        self.dt_last_done = dt;
+        self.er.clone_from(&self.e);
+        self.br.clone_from(&self.b);
+        self.predict_next_step(dt_new / dt, false);
+        Ok(dt_new)
+    }
+
+    // extrapolates this step's series to the next one as a starting guess for
+    // the predictor-corrector loop. A rejected step predicts from the last
+    // accepted step instead of the failed attempt.
+    fn predict_next_step(&mut self, ratio: Float, from_last_done: bool) {
+        let n = self.b[0].len();
+        if ratio > 20.0 {
+            // too far out for the old series to be any use
+            for k in 0..7 {
+                self.b[k] = vec![Vector::ZERO; n];
+                self.e[k] = vec![Vector::ZERO; n];
+            }
+            return;
+        }
+        let (b, e) = if from_last_done {
+            (self.br.clone(), self.er.clone())
+        } else {
+            (self.b.clone(), self.e.clone())
+        };
+        for i in 0..n {
+            let mut q = 1.0;
+            for k in 0..7 {
+                q *= ratio;
+                // e_k = q^(k+1) * sum over j >= k of binomial(j+1, k+1) * b_j
+                let sum: Vector = (k..7)
+                    .map(|j| binomial(j + 1, k + 1) as Float * b[j][i])
+                    .sum();
+                self.e[k][i] = q * sum;
+                self.b[k][i] = self.e[k][i] + (b[k][i] - e[k][i]);
+            }
+        }
+    }
+}
+
+impl Default for Ias15 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Integrator for Ias15 {
+    fn step(
+        &mut self,
+        pos: &mut [Vector],
+        vel: &mut [Vector],
+        timestep: Float,
+        accel: &AccelFn,
+    ) -> Vec<Vector> {
+        if self.csx.len() != pos.len() {
+            // bodies were added or merged, the stored series no longer line up
+            self.reset(pos.len());
+        }
+        let mut remaining = timestep;
+        while remaining > 0.0 {
+            // never go below what the time left can still resolve
+            let floor = self.min_dt.max(remaining * 2.0 * Float::EPSILON);
+            let mut dt = if self.dt_try > 0.0 {
+                self.dt_try.max(floor).min(remaining)
+            } else {
+                remaining
+            };
+            loop {
+                match self.substep(pos, vel, dt, accel, dt <= floor) {
+                    Ok(dt_next) => {
+                        self.dt_try = dt_next;
+                        break;
+                    }
+                    Err(dt_retry) => dt = dt_retry.max(floor),
+                }
+            }
+            remaining -= dt;
+        }
+        accel(pos, vel)
+    }
+
+    fn name(&self) -> &'static str {
+        "IAS15"
+    }
+}
+
+fn add_compensated(sum: &mut Vector, compensation: &mut Vector, delta: Vector) {
+    let old = *sum;
+    *compensation += delta;
+    *sum = old + *compensation;
+    *compensation += old - *sum;
+}
+
+fn binomial(n: usize, k: usize) -> usize {
+    (0..k).fold(1, |acc, i| acc * (n - i) / (i + 1))
+}
+
+fn conversion_coefficients() -> ([[Float; 7]; 7], [[Float; 7]; 7]) {
+    // acceleration over the step in Newton form, a0 + sum g[j] * prod_{i<=j} (h - H[i]),
+    // and as a power series, a0 + sum b[k] * h^(k+1). c maps one onto the other.
+    let mut c = [[0.0_f64; 7]; 7];
+    let mut poly = vec![0.0_f64, 1.0];
+    for j in 0..7 {
+        if j > 0 {
+            // multiply by (h - H[j])
+            let mut next = vec![0.0; poly.len() + 1];
+            for (p, coeff) in poly.iter().enumerate() {
+                next[p + 1] += coeff;
+                next[p] -= coeff * H[j];
+            }
+            poly = next;
+        }
+        for k in 0..=j {
+            c[k][j] = poly[k + 1];
+        }
+    }
+    // c is unit upper triangular, so d = c^-1 follows by back substitution,
+    // one column at a time
+    let mut columns = [[0.0_f64; 7]; 7];
+    for (k, column) in columns.iter_mut().enumerate() {
+        column[k] = 1.0;
+        for j in (0..k).rev() {
+            column[j] = -(j + 1..=k).map(|m| c[j][m] * column[m]).sum::<f64>();
+        }
+    }
+    let d: [[f64; 7]; 7] = std::array::from_fn(|j| std::array::from_fn(|k| columns[k][j]));
+    (
+        c.map(|row| row.map(|x| x as Float)),
+        d.map(|row| row.map(|x| x as Float)),
+    )
+}
@@ -0,0 +1,327 @@
+use crate::ias15::Ias15;
+use crate::physics::GravityModel;
+use crate::scalar::{Float, Vector};
+use crate::wisdom_holman::WisdomHolman;
+
+// Computes the acceleration of every body from the current positions and velocities.
+pub type AccelFn<'f> = dyn Fn(&[Vector], &[Vector]) -> Vec<Vector> + 'f;
+
+pub trait Integrator {
+    // Called before every step with the law of gravity and the masses of the
+    // bodies, for schemes that need them to split the motion into known orbits.
+    fn prepare(&mut self, _gravity: &GravityModel, _mass: &[Float]) {}
+
+    // Advances pos and vel by one timestep and returns the last acceleration
+    // evaluation, which the system keeps on each planet for rendering.
+    fn step(
+        &mut self,
+        pos: &mut [Vector],
+        vel: &mut [Vector],
+        timestep: Float,
+        accel: &AccelFn,
+    ) -> Vec<Vector>;
+
+    fn name(&self) -> &'static str;
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum TimestepMode {
+    // every update is a single step of the system timestep
+    Fixed,
+    // the system timestep is covered by substeps of eta * min sqrt(|a|/|jerk|)
+    // (Aarseth's acceleration criterion), clamped to [min_step, max_step]
+    Adaptive {
+        eta: Float,
+        min_step: Float,
+        max_step: Float,
+    },
+}
+
+impl TimestepMode {
+    pub fn adaptive(min_step: Float, max_step: Float) -> TimestepMode {
+        let mode = TimestepMode::Adaptive {
+            eta: 0.02,
+            min_step,
+            max_step,
+        };
+        mode.validate();
+        mode
+    }
+
+    // panics unless every adaptive step is positive, a step of zero would never
+    // get through an update
+    pub fn validate(&self) {
+        if let TimestepMode::Adaptive {
+            min_step, max_step, ..
+        } = *self
+        {
+            assert!(
+                0.0 < min_step && min_step <= max_step,
+                "adaptive steps need 0 < min_step <= max_step, got {} and {}",
+                min_step,
+                max_step
+            );
+        }
+    }
+}
+
+pub fn aarseth_timestep(accel: &[Vector], jerk: &[Vector], eta: Float) -> Float {
+    // bodies whose acceleration changes quickly relative to its size, i.e. those
+    // in a close encounter, set the step for the whole system. One passing
+    // through a point where the pulls on it cancel says nothing about that.
+    accel
+        .iter()
+        .zip(jerk)
+        .filter(|(a, j)| a.length() > 0.0 && j.length() > 0.0)
+        .map(|(a, j)| eta * (a.length() / j.length()).sqrt())
+        .fold(Float::INFINITY, Float::min)
+}
+
+pub fn integrator_from_name(name: &str) -> Option<Box<dyn Integrator>> {
+    match name {
+        "euler" => Some(Box::new(SemiImplicitEuler)),
+        "verlet" => Some(Box::new(VelocityVerlet)),
+        "leapfrog" => Some(Box::new(Leapfrog)),
+        "rk4" => Some(Box::new(RungeKutta4)),
+        "yoshida4" => Some(Box::new(Yoshida4)),
+        "yoshida6" => Some(Box::new(Yoshida6)),
+        "forest-ruth" => Some(Box::new(ForestRuth)),
+        "ias15" => Some(Box::new(Ias15::new())),
+        "wisdom-holman" => Some(Box::new(WisdomHolman::new())),
+        _ => None,
+    }
+}
+
+fn kick(vel: &mut [Vector], accel: &[Vector], dt: Float) {
+    for (v, a) in vel.iter_mut().zip(accel) {
+        *v += *a * dt;
+    }
+}
+
+fn drift(pos: &mut [Vector], vel: &[Vector], dt: Float) {
+    for (p, v) in pos.iter_mut().zip(vel) {
+        *p += *v * dt;
+    }
+}
+
+// vel += accel*dt; pos += vel*dt, the original Planet::update scheme
+pub struct SemiImplicitEuler;
+
+impl Integrator for SemiImplicitEuler {
+    fn step(
+        &mut self,
+        pos: &mut [Vector],
+        vel: &mut [Vector],
+        timestep: Float,
+        accel: &AccelFn,
+    ) -> Vec<Vector> {
+        let a = accel(pos, vel);
+        kick(vel, &a, timestep);
+        drift(pos, vel, timestep);
+        a
+    }
+
+    fn name(&self) -> &'static str {
+        "Semi-implicit Euler"
+    }
+}
+
+pub struct VelocityVerlet;
+
+impl Integrator for VelocityVerlet {
+    fn step(
+        &mut self,
+        pos: &mut [Vector],
+        vel: &mut [Vector],
+        timestep: Float,
+        accel: &AccelFn,
+    ) -> Vec<Vector> {
+        // x' = x + v*dt + a*dt^2/2, v' = v + (a + a')*dt/2
+        let a = accel(pos, vel);
+        for ((p, v), a) in pos.iter_mut().zip(vel.iter()).zip(&a) {
+            *p += *v * timestep + *a * (0.5 * timestep * timestep);
+        }
+        let new_a = accel(pos, vel);
+        for ((v, a), new_a) in vel.iter_mut().zip(&a).zip(&new_a) {
+            *v += (*a + *new_a) * (0.5 * timestep);
+        }
+        new_a
+    }
+
+    fn name(&self) -> &'static str {
+        "Velocity Verlet"
+    }
+}
+
+// kick-drift-kick leapfrog
+pub struct Leapfrog;
+
+impl Integrator for Leapfrog {
+    fn step(
+        &mut self,
+        pos: &mut [Vector],
+        vel: &mut [Vector],
+        timestep: Float,
+        accel: &AccelFn,
+    ) -> Vec<Vector> {
+        let a = accel(pos, vel);
+        kick(vel, &a, 0.5 * timestep);
+        drift(pos, vel, timestep);
+        let a = accel(pos, vel);
+        kick(vel, &a, 0.5 * timestep);
+        a
+    }
+
+    fn name(&self) -> &'static str {
+        "Leapfrog (KDK)"
+    }
+}
+
+// classic fourth order Runge-Kutta on the state (pos, vel)
+pub struct RungeKutta4;
+
+impl Integrator for RungeKutta4 {
+    fn step(
+        &mut self,
+        pos: &mut [Vector],
+        vel: &mut [Vector],
+        timestep: Float,
+        accel: &AccelFn,
+    ) -> Vec<Vector> {
+        let offset = |base: &[Vector], delta: &[Vector], dt: Float| -> Vec<Vector> {
+            base.iter().zip(delta).map(|(b, d)| *b + *d * dt).collect()
+        };
+
+        let k1_x = vel.to_vec();
+        let k1_v = accel(pos, vel);
+
+        let x2 = offset(pos, &k1_x, 0.5 * timestep);
+        let k2_x = offset(vel, &k1_v, 0.5 * timestep);
+        let k2_v = accel(&x2, &k2_x);
+
+        let x3 = offset(pos, &k2_x, 0.5 * timestep);
+        let k3_x = offset(vel, &k2_v, 0.5 * timestep);
+        let k3_v = accel(&x3, &k3_x);
+
+        let x4 = offset(pos, &k3_x, timestep);
+        let k4_x = offset(vel, &k3_v, timestep);
+        let k4_v = accel(&x4, &k4_x);
+
+        for i in 0..pos.len() {
+            pos[i] += (k1_x[i] + 2.0 * k2_x[i] + 2.0 * k3_x[i] + k4_x[i]) * (timestep / 6.0);
+            vel[i] += (k1_v[i] + 2.0 * k2_v[i] + 2.0 * k3_v[i] + k4_v[i]) * (timestep / 6.0);
+        }
+        accel(pos, vel)
+    }
+
+    fn name(&self) -> &'static str {
+        "Runge-Kutta 4"
+    }
+}
+
+// runs KDK leapfrog substeps of length w*timestep for each weight, reusing the
+// closing acceleration of one substep as the opening kick of the next
+fn compose_leapfrog(
+    weights: &[Float],
+    pos: &mut [Vector],
+    vel: &mut [Vector],
+    timestep: Float,
+    accel: &AccelFn,
+) -> Vec<Vector> {
+    let mut a = accel(pos, vel);
+    for w in weights {
+        kick(vel, &a, 0.5 * w * timestep);
+        drift(pos, vel, w * timestep);
+        a = accel(pos, vel);
+        kick(vel, &a, 0.5 * w * timestep);
+    }
+    a
+}
+
+// Yoshida (1990) triple jump: 4th order, three leapfrog substeps
+pub struct Yoshida4;
+
+impl Yoshida4 {
+    pub fn weights() -> [Float; 3] {
+        let cbrt2 = 2.0_f64.cbrt();
+        let w1 = 1.0 / (2.0 - cbrt2);
+        let w0 = -cbrt2 / (2.0 - cbrt2);
+        [w1 as Float, w0 as Float, w1 as Float]
+    }
+}
+
+impl Integrator for Yoshida4 {
+    fn step(
+        &mut self,
+        pos: &mut [Vector],
+        vel: &mut [Vector],
+        timestep: Float,
+        accel: &AccelFn,
+    ) -> Vec<Vector> {
+        compose_leapfrog(&Self::weights(), pos, vel, timestep, accel)
+    }
+
+    fn name(&self) -> &'static str {
+        "Yoshida 4th order"
+    }
+}
+
+// Yoshida (1990) solution A: 6th order, seven leapfrog substeps
+pub struct Yoshida6;
+
+impl Yoshida6 {
+    pub fn weights() -> [Float; 7] {
+        let w1 = -1.177_679_984_178_87_f64;
+        let w2 = 0.235_573_213_359_357_f64;
+        let w3 = 0.784_513_610_477_560_f64;
+        let w0 = 1.0 - 2.0 * (w1 + w2 + w3);
+        [w3, w2, w1, w0, w1, w2, w3].map(|w| w as Float)
+    }
+}
+
+impl Integrator for Yoshida6 {
+    fn step(
+        &mut self,
+        pos: &mut [Vector],
+        vel: &mut [Vector],
+        timestep: Float,
+        accel: &AccelFn,
+    ) -> Vec<Vector> {
+        compose_leapfrog(&Self::weights(), pos, vel, timestep, accel)
+    }
+
+    fn name(&self) -> &'static str {
+        "Yoshida 6th order"
+    }
+}
+
+// Forest-Ruth (1990) in its original drift-kick form: the same triple jump as
+// Yoshida4 but built on drift-kick-drift, so positions are advanced first
+pub struct ForestRuth;
+
+impl Integrator for ForestRuth {
+    fn step(
+        &mut self,
+        pos: &mut [Vector],
+        vel: &mut [Vector],
+        timestep: Float,
+        accel: &AccelFn,
+    ) -> Vec<Vector> {
+        let theta = Yoshida4::weights()[0];
+        drift(pos, vel, 0.5 * theta * timestep);
+        let a = accel(pos, vel);
+        kick(vel, &a, theta * timestep);
+        drift(pos, vel, 0.5 * (1.0 - theta) * timestep);
+        let a = accel(pos, vel);
+        kick(vel, &a, (1.0 - 2.0 * theta) * timestep);
+        drift(pos, vel, 0.5 * (1.0 - theta) * timestep);
+        let a = accel(pos, vel);
+        kick(vel, &a, theta * timestep);
+        drift(pos, vel, 0.5 * theta * timestep);
+        a
+    }
+
+    fn name(&self) -> &'static str {
+        "Forest-Ruth"
+    }
+}
@@ -1,6 +1,20 @@
+pub mod bodies;
+pub mod collision;
+pub mod diagnostics;
+pub mod fmm;
+pub mod forces;
+pub mod ias15;
+pub mod integrator;
+pub mod orbit;
+pub mod parallel;
 pub mod physics;
 pub mod planet;
+pub mod quadtree;
+pub mod scalar;
 pub mod system;
+pub mod tides;
+pub mod units;
 pub mod util;
+pub mod wisdom_holman;
 
 use crate::util::*;
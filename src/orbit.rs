@@ -0,0 +1,296 @@
+use crate::bodies::BodyId;
+use crate::physics::GravityModel;
+use crate::planet::Planet;
+use crate::scalar::{Float, Vector};
+
+// The Keplerian orbit of one body about another in the plane, unsoftened.
+// Bound orbits have e < 1 and a > 0, unbound ones e > 1 and a < 0; parabolic
+// orbits, e = 1 exactly, are not representable. The argument of periapsis is
+// measured from the x axis, and the anomaly in the direction of motion, which
+// is anticlockwise unless the orbit is retrograde.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct OrbitalElements {
+    pub semi_major_axis: Float,
+    pub eccentricity: Float,
+    pub argument_of_periapsis: Float,
+    pub mean_anomaly: Float,
+    pub retrograde: bool,
+}
+
+// G (M + m), what the relative orbit of two bodies depends on
+pub fn gravitational_parameter(gravity: &GravityModel, primary_mass: Float, mass: Float) -> Float {
+    gravity.g * (primary_mass + mass)
+}
+
+impl OrbitalElements {
+    // an anticlockwise orbit
+    pub fn new(
+        semi_major_axis: Float,
+        eccentricity: Float,
+        argument_of_periapsis: Float,
+        mean_anomaly: Float,
+    ) -> OrbitalElements {
+        OrbitalElements {
+            semi_major_axis,
+            eccentricity,
+            argument_of_periapsis,
+            mean_anomaly,
+            retrograde: false,
+        }
+    }
+
+    pub fn circular(radius: Float, angle: Float) -> OrbitalElements {
+        Self::new(radius, 0.0, 0.0, angle)
+    }
+
+    // elements of the orbit of body about primary
+    pub fn between(gravity: &GravityModel, body: &Planet, primary: &Planet) -> OrbitalElements {
+        Self::from_state(
+            body.pos - primary.pos,
+            body.vel - primary.vel,
+            gravitational_parameter(gravity, primary.mass, body.mass),
+        )
+    }
+
+    // from a position and velocity relative to the primary, mu = G (M + m).
+    // Circular orbits have no periapsis, theirs is put on the x axis.
+    pub fn from_state(pos: Vector, vel: Vector, mu: Float) -> OrbitalElements {
+        let r = pos.length();
+        let h = pos.perp_dot(vel);
+        let energy = 0.5 * vel.length_squared() - mu / r;
+        let semi_major_axis = -mu / (2.0 * energy);
+        let e_vec = ((vel.length_squared() - mu / r) * pos - pos.dot(vel) * vel) / mu;
+        let eccentricity = e_vec.length();
+        let argument_of_periapsis = if eccentricity > Float::EPSILON {
+            e_vec.y.atan2(e_vec.x)
+        } else {
+            0.0
+        };
+        let periapsis = Vector::new(argument_of_periapsis.cos(), argument_of_periapsis.sin());
+        let direction = if h < 0.0 { -1.0 } else { 1.0 };
+        let true_anomaly = direction * periapsis.perp_dot(pos).atan2(periapsis.dot(pos));
+        let e = eccentricity;
+        let (s, c) = (true_anomaly.sin(), true_anomaly.cos());
+        let mean_anomaly = if e < 1.0 {
+            let anomaly = ((1.0 - e * e).sqrt() * s).atan2(e + c);
+            anomaly - e * anomaly.sin()
+        } else {
+            let anomaly = ((e * e - 1.0).sqrt() * s / (1.0 + e * c)).asinh();
+            e * anomaly.sinh() - anomaly
+        };
+        OrbitalElements {
+            semi_major_axis,
+            eccentricity,
+            argument_of_periapsis,
+            mean_anomaly,
+            retrograde: h < 0.0,
+        }
+    }
+
+    // position and velocity relative to the primary, mu = G (M + m)
+    pub fn to_state(&self, mu: Float) -> (Vector, Vector) {
+        let e = self.eccentricity;
+        let a = self.semi_major_axis.abs();
+        let n = (mu / (a * a * a)).sqrt();
+        let anomaly = solve_kepler(self.mean_anomaly, e);
+        // in the frame with periapsis along x
+        let (pos, vel) = if e < 1.0 {
+            let (s, c) = (anomaly.sin(), anomaly.cos());
+            let b = (1.0 - e * e).sqrt();
+            let rate = n / (1.0 - e * c);
+            (
+                a * Vector::new(c - e, b * s),
+                a * rate * Vector::new(-s, b * c),
+            )
+        } else {
+            let (s, c) = (anomaly.sinh(), anomaly.cosh());
+            let b = (e * e - 1.0).sqrt();
+            let rate = n / (e * c - 1.0);
+            (
+                a * Vector::new(e - c, b * s),
+                a * rate * Vector::new(-s, b * c),
+            )
+        };
+        let (pos, vel) = if self.retrograde {
+            (Vector::new(pos.x, -pos.y), Vector::new(vel.x, -vel.y))
+        } else {
+            (pos, vel)
+        };
+        let rotation = Vector::new(
+            self.argument_of_periapsis.cos(),
+            self.argument_of_periapsis.sin(),
+        );
+        (rotation.rotate(pos), rotation.rotate(vel))
+    }
+
+    // radians per unit time
+    pub fn mean_motion(&self, mu: Float) -> Float {
+        let a = self.semi_major_axis.abs();
+        (mu / (a * a * a)).sqrt()
+    }
+
+    // none for unbound orbits
+    pub fn period(&self, mu: Float) -> Option<Float> {
+        let tau = std::f64::consts::TAU as Float;
+        (self.eccentricity < 1.0).then_some(tau / self.mean_motion(mu))
+    }
+
+    pub fn periapsis(&self) -> Float {
+        self.semi_major_axis * (1.0 - self.eccentricity)
+    }
+
+    // none for unbound orbits
+    pub fn apoapsis(&self) -> Option<Float> {
+        (self.eccentricity < 1.0).then_some(self.semi_major_axis * (1.0 + self.eccentricity))
+    }
+}
+
+// The eccentric anomaly E with M = E - e sin E, or for e > 1 the hyperbolic
+// anomaly H with M = e sinh H - H, by Newton's method
+pub fn solve_kepler(mean_anomaly: Float, eccentricity: Float) -> Float {
+    let e = eccentricity;
+    let tolerance = 4.0 * Float::EPSILON;
+    if e < 1.0 {
+        let pi = std::f64::consts::PI as Float;
+        // the same orbit for every turn, keep M in [-pi, pi)
+        let turns = ((mean_anomaly + pi) / (2.0 * pi)).floor();
+        let m = mean_anomaly - 2.0 * pi * turns;
+        let mut anomaly = if e > 0.8 { pi.copysign(m) } else { m };
+        for _ in 0..50 {
+            let step = (anomaly - e * anomaly.sin() - m) / (1.0 - e * anomaly.cos());
+            anomaly -= step;
+            if step.abs() <= tolerance * (1.0 + anomaly.abs()) {
+                break;
+            }
+        }
+        anomaly + 2.0 * pi * turns
+    } else {
+        let m = mean_anomaly;
+        let mut anomaly = (m / e).asinh();
+        for _ in 0..50 {
+            let step = (e * anomaly.sinh() - anomaly - m) / (e * anomaly.cosh() - 1.0);
+            anomaly -= step;
+            if step.abs() <= tolerance * (1.0 + anomaly.abs()) {
+                break;
+            }
+        }
+        anomaly
+    }
+}
+
+// Carries a body at pos moving with vel relative to its primary, mu = G (M + m),
+// dt along its Kepler orbit, dt may be negative. Uses the f and g functions in
+// the change of eccentric (or hyperbolic) anomaly, which stay well behaved for
+// circular orbits; parabolic ones, zero energy exactly, are not handled.
+pub fn propagate(pos: Vector, vel: Vector, mu: Float, dt: Float) -> (Vector, Vector) {
+    let r0 = pos.length();
+    let energy = 0.5 * vel.length_squared() - mu / r0;
+    let tolerance = 4.0 * Float::EPSILON;
+    // semi-major axis, or its magnitude for unbound orbits
+    let a = (mu / (2.0 * energy)).abs();
+    let sqrt_a = a.sqrt();
+    let sigma = pos.dot(vel) / mu.sqrt();
+    let n = (mu / (a * a * a)).sqrt();
+    let (f, g, fdot, gdot) = if energy < 0.0 {
+        let pi = std::f64::consts::PI as Float;
+        // whole orbits change nothing, solve for what is left of one
+        let mean = n * dt;
+        let turns = ((mean + pi) / (2.0 * pi)).floor();
+        let m = mean - 2.0 * pi * turns;
+        let mut x = m;
+        for _ in 0..100 {
+            let (s, c) = x.sin_cos();
+            let value = x + sigma / sqrt_a * (1.0 - c) - (1.0 - r0 / a) * s - m;
+            let slope = 1.0 + sigma / sqrt_a * s - (1.0 - r0 / a) * c;
+            let step = value / slope;
+            x -= step;
+            if step.abs() <= tolerance * (1.0 + x.abs()) {
+                break;
+            }
+        }
+        x += 2.0 * pi * turns;
+        let (s, c) = x.sin_cos();
+        let r = a + (r0 - a) * c + sigma * sqrt_a * s;
+        (
+            1.0 - a / r0 * (1.0 - c),
+            dt + (s - x) / n,
+            -(mu * a).sqrt() * s / (r * r0),
+            1.0 - a / r * (1.0 - c),
+        )
+    } else {
+        let m = n * dt;
+        let mut x = (m / (1.0 + r0 / a)).asinh();
+        for _ in 0..100 {
+            let (s, c) = (x.sinh(), x.cosh());
+            let value = sigma / sqrt_a * (c - 1.0) + (1.0 + r0 / a) * s - x - m;
+            let slope = sigma / sqrt_a * s + (1.0 + r0 / a) * c - 1.0;
+            let step = value / slope;
+            x -= step;
+            if step.abs() <= tolerance * (1.0 + x.abs()) {
+                break;
+            }
+        }
+        let (s, c) = (x.sinh(), x.cosh());
+        let r = a * (c - 1.0) + r0 * c + sigma * sqrt_a * s;
+        (
+            1.0 - a / r0 * (c - 1.0),
+            dt - (s - x) / n,
+            -(mu * a).sqrt() * s / (r * r0),
+            1.0 - a / r * (c - 1.0),
+        )
+    };
+    (f * pos + g * vel, fdot * pos + gdot * vel)
+}
+
+// The analytic orbit of one body about another from the moment the reference
+// was taken, to measure how far an integration strays from it
+#[derive(Clone, PartialEq, Debug)]
+pub struct KeplerReference {
+    pub body: BodyId,
+    pub primary: BodyId,
+    start: Float,
+    pos: Vector,
+    vel: Vector,
+    mu: Float,
+    // time and distance between the integrated and the analytic position of
+    // body relative to primary
+    errors: Vec<(Float, Float)>,
+}
+
+impl KeplerReference {
+    pub fn new(
+        gravity: &GravityModel,
+        body: BodyId,
+        body_state: &Planet,
+        primary: BodyId,
+        primary_state: &Planet,
+        time: Float,
+    ) -> KeplerReference {
+        KeplerReference {
+            body,
+            primary,
+            start: time,
+            pos: body_state.pos - primary_state.pos,
+            vel: body_state.vel - primary_state.vel,
+            mu: gravitational_parameter(gravity, primary_state.mass, body_state.mass),
+            errors: vec![],
+        }
+    }
+
+    // position and velocity of body relative to primary at time on the orbit
+    pub fn state_at(&self, time: Float) -> (Vector, Vector) {
+        propagate(self.pos, self.vel, self.mu, time - self.start)
+    }
+
+    // logs the error of the integrated positions at time
+    pub fn record(&mut self, time: Float, body: &Planet, primary: &Planet) -> Float {
+        let (expected, _) = self.state_at(time);
+        let error = (body.pos - primary.pos).distance(expected);
+        self.errors.push((time, error));
+        error
+    }
+
+    pub fn errors(&self) -> &[(Float, Float)] {
+        &self.errors
+    }
+}
@@ -0,0 +1,19 @@
+// Per-body loops that spread across threads with the "parallel" feature.
+//
+// Each body's result is computed by the same code in the same order either
+// way, only the bodies are shared out between threads, so serial and parallel
+// runs give bit-identical results.
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+// collects f(i) for every body index i in 0..n
+#[cfg(feature = "parallel")]
+pub fn map_bodies<T: Send>(n: usize, f: impl Fn(usize) -> T + Sync + Send) -> Vec<T> {
+    (0..n).into_par_iter().map(f).collect()
+}
+
+#[cfg(not(feature = "parallel"))]
+pub fn map_bodies<T>(n: usize, f: impl Fn(usize) -> T) -> Vec<T> {
+    (0..n).map(f).collect()
+}
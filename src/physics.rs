@@ -1,49 +1,427 @@
+use crate::parallel::map_bodies;
 use crate::planet::Planet;
-use glam::Vec2;
+use crate::scalar::{Float, Vector};
 
-pub fn calc_accel(self_planet: &Planet, planet: &Planet) -> Vec2 {
-    let G: f32 = 6.6 * (10.0_f32).powf(-11.0_f32);
-    // let G: f32 = 0.0;
-    let mut resultant_force = Vec2::new(0.0, 0.0);
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Softening {
+    // plain 1/r^2, infinite when two bodies meet
+    None,
+    // Plummer sphere of scale length epsilon: a = Gm r / (r^2 + epsilon^2)^(3/2)
+    Plummer(Float),
+    // cubic spline kernel of radius h (Monaghan & Lattanzio 1985, as in GADGET):
+    // exactly Newtonian beyond h and finite all the way in
+    Spline(Float),
+}
+
+// The law of gravity every function in this module uses: the gravitational
+// constant, how the force is softened at short range and, when the speed of
+// light is set, the first post-Newtonian correction on top of it.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct GravityModel {
+    pub g: Float,
+    pub softening: Softening,
+    // in simulation units, none for purely Newtonian gravity
+    pub speed_of_light: Option<Float>,
+}
+
+impl GravityModel {
+    pub fn new(g: Float, softening: Softening) -> GravityModel {
+        GravityModel {
+            g,
+            softening,
+            speed_of_light: None,
+        }
+    }
+
+    // the same law with the 1PN correction for light travelling at speed_of_light
+    pub fn post_newtonian(self, speed_of_light: Float) -> GravityModel {
+        GravityModel {
+            speed_of_light: Some(speed_of_light),
+            ..self
+        }
+    }
+
+    // f(r) with a = Gm (source - pos) f, 1/r^3 without softening. Zero at r = 0
+    // so a body never pulls on itself.
+    pub fn force_factor(&self, r2: Float) -> Float {
+        self.jerk_factors(r2).0
+    }
+
+    // f(r) and f'(r) / r, what the acceleration and its time derivative are built from
+    fn jerk_factors(&self, r2: Float) -> (Float, Float) {
+        match self.softening {
+            Softening::Plummer(epsilon) if epsilon > 0.0 => {
+                let s = r2 + epsilon * epsilon;
+                let f = 1.0 / (s * s.sqrt());
+                (f, -3.0 * f / s)
+            }
+            Softening::Spline(h) if h > 0.0 && r2 < h * h => {
+                let u = r2.sqrt() / h;
+                let h3 = h * h * h;
+                if u < 0.5 {
+                    (
+                        (32.0 / 3.0 + u * u * (32.0 * u - 38.4)) / h3,
+                        (96.0 * u - 76.8) / (h3 * h * h),
+                    )
+                } else {
+                    let u3 = u * u * u;
+                    (
+                        (64.0 / 3.0 - 48.0 * u + 38.4 * u * u
+                            - 32.0 / 3.0 * u3
+                            - 1.0 / (15.0 * u3))
+                            / h3,
+                        (-48.0 + 76.8 * u - 32.0 * u * u + 0.2 / (u3 * u)) / (u * h3 * h * h),
+                    )
+                }
+            }
+            _ if r2 > 0.0 => {
+                let f = 1.0 / (r2 * r2.sqrt());
+                (f, -3.0 * f / r2)
+            }
+            _ => (0.0, 0.0),
+        }
+    }
+
+    // potential energy per unit mass at distance sqrt(r2) from a point mass
+    pub fn potential(&self, r2: Float, mass: Float) -> Float {
+        let r = r2.sqrt();
+        let phi = match self.softening {
+            Softening::Plummer(epsilon) if epsilon > 0.0 => 1.0 / (r2 + epsilon * epsilon).sqrt(),
+            Softening::Spline(h) if h > 0.0 && r < h => {
+                let u = r / h;
+                let w = if u < 0.5 {
+                    -2.8 + u * u * (16.0 / 3.0 + u * u * (6.4 * u - 9.6))
+                } else {
+                    -3.2 + 1.0 / (15.0 * u)
+                        + u * u * (32.0 / 3.0 + u * (-16.0 + u * (9.6 - 32.0 / 15.0 * u)))
+                };
+                -w / h
+            }
+            _ => 1.0 / r,
+        };
+        -self.g * mass * phi
+    }
+}
+
+impl Default for GravityModel {
+    // the constant the simulation has always used, unsoftened
+    fn default() -> Self {
+        Self::new(6.6e-11, Softening::None)
+    }
+}
+
+pub fn calc_accel(gravity: &GravityModel, self_planet: &Planet, planet: &Planet) -> Vector {
+    calc_point_accel(gravity, self_planet.pos, planet.pos, planet.mass)
+        + calc_point_pn_accel(
+            gravity,
+            self_planet.pos,
+            self_planet.vel,
+            self_planet.mass,
+            planet.pos,
+            planet.vel,
+            planet.mass,
+        )
+}
+
+pub fn calc_point_accel(
+    gravity: &GravityModel,
+    pos: Vector,
+    source: Vector,
+    mass: Float,
+) -> Vector {
+    // acceleration felt at pos from a point mass at source
     //ma = Gmm/r^2   -> a = Gm/r^2
-    let dist = ((planet.pos.x - self_planet.pos.x).powf(2.0)
-        + (planet.pos.y - self_planet.pos.y).powf(2.0))
-    .sqrt()
-    .abs();
-    let magnitude = (G * self_planet.mass * planet.mass) / dist.powi(2);
-    let force = (planet.pos - self_planet.pos).normalize_or_zero() * magnitude;
-    resultant_force += force;
-    let accel = force / self_planet.mass;
-    accel
-}
-
-pub fn calc_init_orbital_velocity(planet: &Planet, sun: &Planet) -> Vec2 {
-    // V = ((Gm)/r).sqrt()
-    let G: f32 = 6.6 * (10.0_f32).powf(-11.0_f32);
-    let dist = ((sun.pos.x - planet.pos.x).powf(2.0) + (sun.pos.y - planet.pos.y).powf(2.0)).sqrt();
-    let v = ((G * sun.mass) / dist).sqrt();
-    let force = (sun.pos - planet.pos).perp().normalize_or_zero() * v;
-    force
-}
-
-pub fn check_escape_velocity(planet: &Planet, sun: &Planet) -> bool {
-    // checks whether a given planet will escape the orbit of a given sun
-    let G: f32 = 6.6 * (10.0_f32).powf(-11.0_f32);
-    let dist = ((sun.pos.x - planet.pos.x).powf(2.0) + (sun.pos.y - planet.pos.y).powf(2.0))
-        .sqrt()
-        .abs();
-    let escape_vel = ((2.0 * G * sun.mass) / dist).sqrt();
-    if planet.vel.length() > escape_vel {
-        true
-    } else {
-        false
+    let r = source - pos;
+    gravity.g * mass * gravity.force_factor(r.length_squared()) * r
+}
+
+// sources are summed this many at a time, enough to fill AVX-512 registers with f64
+const LANES: usize = 8;
+
+pub fn calc_accelerations(gravity: &GravityModel, pos: &[Vector], mass: &[Float]) -> Vec<Vector> {
+    // direct summation over every pair, O(n^2). The sources are split into x, y
+    // and G*m arrays padded with massless bodies to a multiple of LANES, so the
+    // inner loop works on fixed width blocks the compiler turns into SIMD code.
+    let epsilon2 = match gravity.softening {
+        Softening::None => 0.0,
+        Softening::Plummer(epsilon) => epsilon * epsilon,
+        Softening::Spline(_) => {
+            // the piecewise kernel doesn't vectorise, sum pair by pair instead
+            return map_bodies(pos.len(), |i| {
+                let mut accel = Vector::ZERO;
+                for (&source, &m) in pos.iter().zip(mass) {
+                    accel += calc_point_accel(gravity, pos[i], source, m);
+                }
+                accel
+            });
+        }
+    };
+    let blocks = pos.len().div_ceil(LANES);
+    let mut xs = vec![[0.0; LANES]; blocks];
+    let mut ys = vec![[0.0; LANES]; blocks];
+    let mut gms = vec![[0.0; LANES]; blocks];
+    for (j, (p, m)) in pos.iter().zip(mass).enumerate() {
+        xs[j / LANES][j % LANES] = p.x;
+        ys[j / LANES][j % LANES] = p.y;
+        gms[j / LANES][j % LANES] = gravity.g * m;
     }
+
+    map_bodies(pos.len(), |i| {
+        let (ax, ay) = sum_blocks(pos[i], epsilon2, &xs, &ys, &gms);
+        Vector::new(ax.iter().sum(), ay.iter().sum())
+    })
 }
 
-pub fn check_collision(self_planet: &Planet, planet: &Planet) -> bool {
-    let check = (self_planet.pos.distance(planet.pos) <= self_planet.radius + planet.radius);
-    if check == true {
-        println!("Collision detected!")
+// per lane sums of the acceleration at p from blocks of sources. Kept out of
+// line: once inlined next to the final horizontal sum LLVM stops vectorising it
+#[inline(never)]
+fn sum_blocks(
+    p: Vector,
+    epsilon2: Float,
+    xs: &[[Float; LANES]],
+    ys: &[[Float; LANES]],
+    gms: &[[Float; LANES]],
+) -> ([Float; LANES], [Float; LANES]) {
+    let mut ax = [0.0; LANES];
+    let mut ay = [0.0; LANES];
+    for ((x, y), gm) in xs.iter().zip(ys).zip(gms) {
+        for l in 0..LANES {
+            let dx = x[l] - p.x;
+            let dy = y[l] - p.y;
+            let r2 = dx * dx + dy * dy + epsilon2;
+            // the body itself sits at r = 0 and adds nothing
+            let inv_r3 = gm[l] / (r2 * r2.sqrt());
+            let inv_r3 = if r2 > 0.0 { inv_r3 } else { 0.0 };
+            ax[l] += dx * inv_r3;
+            ay[l] += dy * inv_r3;
+        }
     }
-    check
+    (ax, ay)
+}
+
+// Computes the gravitational acceleration of every body from positions and masses.
+pub trait ForceSolver {
+    fn accelerations(&self, gravity: &GravityModel, pos: &[Vector], mass: &[Float]) -> Vec<Vector>;
+
+    // acceleration at each of points from the bodies, for test particles that
+    // feel the bodies without pulling on them. A direct sum over the bodies,
+    // O(points * bodies), unless a solver knows better.
+    fn field(
+        &self,
+        gravity: &GravityModel,
+        points: &[Vector],
+        pos: &[Vector],
+        mass: &[Float],
+    ) -> Vec<Vector> {
+        calc_field(gravity, points, pos, mass)
+    }
+
+    fn name(&self) -> &'static str;
+}
+
+// exact pairwise sum, the reference every approximate solver is checked against
+pub struct DirectSum;
+
+impl ForceSolver for DirectSum {
+    fn accelerations(&self, gravity: &GravityModel, pos: &[Vector], mass: &[Float]) -> Vec<Vector> {
+        calc_accelerations(gravity, pos, mass)
+    }
+
+    fn name(&self) -> &'static str {
+        "Direct sum"
+    }
+}
+
+pub fn calc_field(
+    gravity: &GravityModel,
+    points: &[Vector],
+    pos: &[Vector],
+    mass: &[Float],
+) -> Vec<Vector> {
+    map_bodies(points.len(), |i| {
+        let mut accel = Vector::ZERO;
+        for (&source, &m) in pos.iter().zip(mass) {
+            accel += calc_point_accel(gravity, points[i], source, m);
+        }
+        accel
+    })
+}
+
+pub fn calc_point_pn_accel(
+    gravity: &GravityModel,
+    pos: Vector,
+    vel: Vector,
+    self_mass: Float,
+    source: Vector,
+    source_vel: Vector,
+    mass: Float,
+) -> Vector {
+    // first post-Newtonian correction to calc_point_accel for a body of
+    // self_mass pulled by one other body and nothing else, zero unless the
+    // speed of light is set. With two bodies the potentials and accelerations
+    // in eih_accel come from each other alone. The relative orbit precesses by
+    // 6 pi G(m1 + m2) / (c^2 a (1 - e^2)) per revolution.
+    let Some(c) = gravity.speed_of_light else {
+        return Vector::ZERO;
+    };
+    let r = pos - source;
+    let r2 = r.length_squared();
+    if r2 == 0.0 {
+        return Vector::ZERO;
+    }
+    let distance = r2.sqrt();
+    eih_accel(
+        c * c,
+        (pos, vel, gravity.g * mass / distance),
+        (source, source_vel, gravity.g * self_mass / distance),
+        gravity.g * mass,
+        gravity.g * self_mass * r / (r2 * distance),
+    )
+}
+
+// One source's term of the Einstein-Infeld-Hoffmann equations of motion
+// (harmonic coordinates), the 1PN correction without the Newtonian pull:
+// a_i = Gm_j / (c^2 r^3) * ((r_j - r_i) * (v_i^2 + 2 v_j^2 - 4 v_i.v_j - 4 U_i - U_j
+//       - 3/2 (n.v_j)^2 + 1/2 (r_j - r_i).a_j) + (r.(4 v_i - 3 v_j)) (v_i - v_j))
+//       + 7/2 Gm_j a_j / (c^2 r)
+// where r = r_i - r_j, n = r/|r|, U is the Newtonian potential (sum of Gm/r
+// over every other body) at each body and a_j is the Newtonian acceleration
+// of the source. Each body is (position, velocity, U).
+fn eih_accel(
+    c2: Float,
+    (pos, vel, potential): (Vector, Vector, Float),
+    (source, source_vel, source_potential): (Vector, Vector, Float),
+    gm: Float,
+    source_accel: Vector,
+) -> Vector {
+    let r = pos - source;
+    let r2 = r.length_squared();
+    if r2 == 0.0 {
+        return Vector::ZERO;
+    }
+    let distance = r2.sqrt();
+    let radial = r.dot(source_vel) / distance;
+    let factor = vel.length_squared() + 2.0 * source_vel.length_squared()
+        - 4.0 * vel.dot(source_vel)
+        - 4.0 * potential
+        - source_potential
+        - 1.5 * radial * radial
+        - 0.5 * r.dot(source_accel);
+    gm / (c2 * r2 * distance)
+        * (-factor * r + r.dot(4.0 * vel - 3.0 * source_vel) * (vel - source_vel))
+        + 3.5 * gm / (c2 * distance) * source_accel
+}
+
+pub fn calc_pn_accelerations(
+    gravity: &GravityModel,
+    pos: &[Vector],
+    vel: &[Vector],
+    mass: &[Float],
+) -> Vec<Vector> {
+    // the correction from the Einstein-Infeld-Hoffmann equations, O(n^2), to
+    // be added to the Newtonian accelerations. Unlike a sum of test particle
+    // terms it keeps the (1PN) momentum of comparable masses. It is
+    // conservative, so binaries precess but never inspiral. The potentials and
+    // accelerations it needs are those of unsoftened point masses.
+    let Some(c) = gravity.speed_of_light else {
+        return vec![Vector::ZERO; pos.len()];
+    };
+    // every term is proportional to the source's mass
+    let sources: Vec<usize> = (0..pos.len()).filter(|&j| mass[j] != 0.0).collect();
+    let fields = map_bodies(pos.len(), |i| {
+        let mut potential = 0.0;
+        let mut accel = Vector::ZERO;
+        for &j in sources.iter() {
+            let r = pos[j] - pos[i];
+            let r2 = r.length_squared();
+            if i == j || r2 == 0.0 {
+                continue;
+            }
+            let distance = r2.sqrt();
+            potential += gravity.g * mass[j] / distance;
+            accel += gravity.g * mass[j] * r / (r2 * distance);
+        }
+        (potential, accel)
+    });
+    map_bodies(pos.len(), |i| {
+        let mut accel = Vector::ZERO;
+        for &j in sources.iter() {
+            if i == j {
+                continue;
+            }
+            accel += eih_accel(
+                c * c,
+                (pos[i], vel[i], fields[i].0),
+                (pos[j], vel[j], fields[j].0),
+                gravity.g * mass[j],
+                fields[j].1,
+            );
+        }
+        accel
+    })
+}
+
+pub fn calc_point_jerk(
+    gravity: &GravityModel,
+    pos: Vector,
+    vel: Vector,
+    source: Vector,
+    source_vel: Vector,
+    mass: Float,
+) -> Vector {
+    // time derivative of calc_point_accel
+    // j = Gm * (v f(r) + r (r.v) f'(r)/r), which is Gm * (v/r^3 - 3(r.v)r/r^5) unsoftened
+    let r = source - pos;
+    let v = source_vel - vel;
+    let (f, df) = gravity.jerk_factors(r.length_squared());
+    gravity.g * mass * (v * f + r * (r.dot(v) * df))
+}
+
+pub fn calc_jerks(
+    gravity: &GravityModel,
+    pos: &[Vector],
+    vel: &[Vector],
+    mass: &[Float],
+) -> Vec<Vector> {
+    // massless bodies pull on nothing
+    let sources: Vec<usize> = (0..pos.len()).filter(|&j| mass[j] != 0.0).collect();
+    map_bodies(pos.len(), |i| {
+        let mut jerk = Vector::ZERO;
+        for &j in sources.iter() {
+            if i == j {
+                continue;
+            }
+            jerk += calc_point_jerk(gravity, pos[i], vel[i], pos[j], vel[j], mass[j]);
+        }
+        jerk
+    })
+}
+
+pub fn calc_init_orbital_velocity(gravity: &GravityModel, planet: &Planet, sun: &Planet) -> Vector {
+    // circular orbit, v^2 / r = a, which is V = ((Gm)/r).sqrt() unsoftened
+    let dist = planet.pos.distance(sun.pos);
+    let accel = calc_point_accel(gravity, planet.pos, sun.pos, sun.mass);
+    let v = (dist * accel.length()).sqrt();
+    (sun.pos - planet.pos).perp().normalize_or_zero() * v
+}
+
+pub fn check_escape_velocity(gravity: &GravityModel, planet: &Planet, sun: &Planet) -> bool {
+    // checks whether a given planet will escape the orbit of a given sun,
+    // v_escape^2 = -2 * potential
+    let potential = gravity.potential(planet.pos.distance_squared(sun.pos), sun.mass);
+    let escape_vel = (-2.0 * potential).sqrt();
+    planet.vel.length() > escape_vel
+}
+
+// Distance from primary inside which its tides tear satellite apart. The
+// classical d = coefficient * R_primary * (rho_primary / rho_satellite)^(1/3)
+// depends on the primary only through its mass once the densities are written
+// out, leaving d = coefficient * r * (M / m)^(1/3). coefficient is 2^(1/3),
+// about 1.26, for a rigid satellite and 2.44 for a fluid one.
+pub fn roche_limit(satellite: &Planet, primary: &Planet, coefficient: Float) -> Float {
+    coefficient * satellite.radius * (primary.mass / satellite.mass).cbrt()
+}
+
+pub fn check_collision(self_planet: &Planet, planet: &Planet) -> bool {
+    self_planet.pos.distance(planet.pos) <= self_planet.radius + planet.radius
 }
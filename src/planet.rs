@@ -1,4 +1,7 @@
+use crate::orbit::{gravitational_parameter, OrbitalElements};
 use crate::physics::*;
+use crate::scalar::{to_screen, Float, Vector};
+use crate::units::Viewport;
 use crate::*;
 use glam::Vec2;
 use pixels::Pixels;
@@ -8,22 +11,22 @@ use rand::Rng;
 #[derive(Copy, Clone, PartialEq)]
 pub struct Planet<'a> {
     pub name: &'a str,
-    pub pos: Vec2,
-    pub radius: f32,
-    pub vel: Vec2,
-    pub mass: f32,
+    pub pos: Vector,
+    pub radius: Float,
+    pub vel: Vector,
+    pub mass: Float,
     pub color: PlanetColor,
-    pub accel: Vec2,
+    pub accel: Vector,
     pub in_collision: bool,
 }
 
 impl<'a> Planet<'a> {
     pub fn new(
         name: &'a str,
-        pos: Vec2,
-        radius: f32,
-        vel: Vec2,
-        mass: f32,
+        pos: Vector,
+        radius: Float,
+        vel: Vector,
+        mass: Float,
         color: PlanetColor,
     ) -> Planet<'a> {
         Planet {
@@ -33,47 +36,81 @@ impl<'a> Planet<'a> {
             vel,
             mass,
             color,
-            accel: Vec2::new(1.0, 1.0),
+            accel: Vector::new(1.0, 1.0),
             in_collision: false,
         }
     }
 
+    // a massless body, pulled by the others without pulling on them
+    pub fn test_particle(
+        name: &'a str,
+        pos: Vector,
+        vel: Vector,
+        color: PlanetColor,
+    ) -> Planet<'a> {
+        Planet::new(name, pos, 0.0, vel, 0.0, color)
+    }
+
+    pub fn is_test_particle(&self) -> bool {
+        self.mass == 0.0
+    }
+
+    // a body on the orbit given by elements about primary, so scenarios can be
+    // written in orbital terms
+    pub fn on_orbit(
+        gravity: &GravityModel,
+        primary: &Planet,
+        name: &'a str,
+        radius: Float,
+        mass: Float,
+        color: PlanetColor,
+        elements: &OrbitalElements,
+    ) -> Planet<'a> {
+        let mu = gravitational_parameter(gravity, primary.mass, mass);
+        let (pos, vel) = elements.to_state(mu);
+        Self::new(
+            name,
+            primary.pos + pos,
+            radius,
+            primary.vel + vel,
+            mass,
+            color,
+        )
+    }
+
+    // the orbit of this body about primary
+    pub fn orbital_elements(&self, gravity: &GravityModel, primary: &Planet) -> OrbitalElements {
+        OrbitalElements::between(gravity, self, primary)
+    }
+
+    // a body on a circular orbit about sun, 25 to 50 of its own radii away in
+    // a random direction
     pub fn create_satellite(
+        gravity: &GravityModel,
         sun: &Planet,
         name: &'a str,
-        radius: f32,
-        mass: f32,
+        radius: Float,
+        mass: Float,
         color: PlanetColor,
     ) -> Planet<'a> {
         let mut rng = rand::thread_rng();
-        let r = rng.gen_range(25.0 * radius..50.0 * radius);
-        let x: f32 = 200.0;
-        if r > 2.5 * radius {
-            let y = sun.pos.x + r;
-        } else {
-            let y = sun.pos.x - r;
-        }
-        let y = (r.powi(2) - x.powi(2)).abs().sqrt();
-        let pos = Vec2::new(x, y);
-
-        let mut new_planet = Self::new(name, pos, radius, Vec2::new(0.0, 0.0), mass, color);
-        let new_velocity = calc_init_orbital_velocity(&new_planet, &sun);
-        new_planet.vel = new_velocity;
-        println!(
-            "{} will escape orbit: {}",
-            new_planet.name,
-            check_escape_velocity(&new_planet, &sun)
-        );
-        new_planet
+        let distance = rng.gen_range(25.0 * radius..50.0 * radius);
+        let angle = rng.gen_range(0.0..std::f64::consts::TAU as Float);
+        let elements = OrbitalElements::circular(distance, angle);
+        Self::on_orbit(gravity, sun, name, radius, mass, color, &elements)
     }
 
-    pub fn render(&self, px: &mut Pixels) {
-        for y in ((self.pos.y - self.radius) as usize)..((self.pos.y + self.radius) as usize) {
-            for x in ((self.pos.x - self.radius) as usize)..((self.pos.x + self.radius) as usize) {
-                let circle_check =
-                    (x as f32 - self.pos.x).powf(2.0) + (y as f32 - self.pos.y).powf(2.0);
-                if (circle_check < self.radius.powf(2.0)) || (circle_check == self.radius.powf(2.0))
-                {
+    pub fn render(&self, px: &mut Pixels, view: &Viewport) {
+        if self.is_test_particle() {
+            self.render_point(px, view);
+            return;
+        }
+        let pos = view.to_screen(self.pos);
+        let radius = view.to_screen_radius(self.radius);
+        for y in ((pos.y - radius) as usize)..((pos.y + radius) as usize) {
+            for x in ((pos.x - radius) as usize)..((pos.x + radius) as usize) {
+                let circle_check = (x as f32 - pos.x).powf(2.0) + (y as f32 - pos.y).powf(2.0);
+                if (circle_check < radius.powf(2.0)) || (circle_check == radius.powf(2.0)) {
                     let index = y * WIDTH * 4 + x * 4 as usize;
                     if index >= (WIDTH * HEIGHT * 4) {
                         continue;
@@ -94,18 +131,31 @@ impl<'a> Planet<'a> {
         }
     }
 
-    pub fn render_force(&self, px: &mut Pixels) {
+    // a single pixel, so thousands of test particles read as a flow
+    pub fn render_point(&self, px: &mut Pixels, view: &Viewport) {
+        let pos = view.to_screen(self.pos);
+        if pos.x < 0.0 || pos.y < 0.0 || pos.x >= WIDTH as f32 || pos.y >= HEIGHT as f32 {
+            return;
+        }
+        let index = pos.y as usize * WIDTH * 4 + pos.x as usize * 4;
+        px.frame_mut()[index..index + 4].copy_from_slice(&[
+            self.color.r,
+            self.color.g,
+            self.color.b,
+            255,
+        ]);
+    }
+
+    pub fn render_force(&self, px: &mut Pixels, view: &Viewport) {
         //in a space thats around the bounding box of a planet x5
         //check if pixels fall on line of vector
         //how do i get the gradient
-        for y in
-            ((self.pos.y - self.radius * 5.0) as usize)..((self.pos.y + self.radius * 5.0) as usize)
-        {
-            for x in ((self.pos.x - self.radius * 5.0) as usize)
-                ..((self.pos.y + self.radius * 5.0) as usize)
-            {
+        let pos = view.to_screen(self.pos);
+        let radius = view.to_screen_radius(self.radius);
+        for y in ((pos.y - radius * 5.0) as usize)..((pos.y + radius * 5.0) as usize) {
+            for x in ((pos.x - radius * 5.0) as usize)..((pos.y + radius * 5.0) as usize) {
                 let thickness = 0.1;
-                let norm = self.vel.normalize_or_zero();
+                let norm = to_screen(self.vel).normalize_or_zero();
                 let gradient = norm.y / norm.x;
                 let line_check = gradient * x as f32;
                 if line_check <= thickness {
@@ -129,15 +179,17 @@ impl<'a> Planet<'a> {
         }
     }
 
-    pub fn render_force2(&self, px: &mut Pixels) {
-        //render acceleration, can be changed to
-        //render velocity
-        let scale = 50.2;
+    // draws the acceleration as a line to where it alone would carry the body
+    // from rest in time, a t^2 / 2, so it reads the same in any units and
+    // zoom. In pixel units a time of 10 is about the old fixed scale.
+    pub fn render_force2(&self, px: &mut Pixels, view: &Viewport, time: Float) {
+        let pos = view.to_screen(self.pos);
+        let end = view.to_screen(self.pos + 0.5 * time * time * self.accel);
 
-        let start_x = self.pos.x;
-        let start_y = self.pos.y;
-        let end_x = (start_x + self.accel.x * scale) as i32;
-        let end_y = (start_y + self.accel.y * scale) as i32;
+        let start_x = pos.x;
+        let start_y = pos.y;
+        let end_x = end.x as i32;
+        let end_y = end.y as i32;
 
         let dx = (end_x - start_x as i32).abs();
         let dy = (end_y - start_y as i32).abs();
@@ -146,8 +198,8 @@ impl<'a> Planet<'a> {
         let sy: i32 = if (start_y as i32) < end_y { 1 } else { -1 };
         let mut err = dx - dy;
 
-        let mut x = self.pos.x as i32;
-        let mut y = self.pos.y as i32;
+        let mut x = pos.x as i32;
+        let mut y = pos.y as i32;
 
         loop {
             if x >= 0 && x < WIDTH as i32 && y >= 0 && y < HEIGHT as i32 {
@@ -172,16 +224,17 @@ impl<'a> Planet<'a> {
         }
     }
 
-    pub fn update(&mut self, timestep: f32, accel: Vec2) {
-        // self.vel += Vec2::new(0.01, 0.01);  //accel
+    pub fn update(&mut self, timestep: Float, accel: Vector) {
+        // self.vel += Vector::new(0.01, 0.01);  //accel
         self.accel = accel;
         self.vel += accel * timestep;
         self.pos += self.vel * timestep;
         // println!("{}",self.vel);
     }
 
-    pub fn calc_energy(&self) -> f32 {
-        0.5 * self.mass * (self.vel * self.vel).length()
+    // kinetic energy only, the system's diagnostics add the potential energy
+    pub fn calc_energy(&self) -> Float {
+        0.5 * self.mass * self.vel.length_squared()
     }
 }
 
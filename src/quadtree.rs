@@ -0,0 +1,278 @@
+use crate::parallel::map_bodies;
+use crate::physics::{calc_point_accel, ForceSolver, GravityModel};
+use crate::scalar::{Float, Vector};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+// leaves stop splitting below this depth so coincident bodies can't recurse forever
+const MAX_DEPTH: usize = 48;
+
+// the top levels of the tree are built on separate threads, down to cells this small
+#[cfg(feature = "parallel")]
+const PARALLEL_DEPTH: usize = 4;
+#[cfg(feature = "parallel")]
+const PARALLEL_MIN_BODIES: usize = 1024;
+
+// A cell of the quadtree. Leaves own the bodies order[start..end], internal
+// nodes own four children stored consecutively from first_child.
+#[derive(Copy, Clone, Debug)]
+pub struct Node {
+    pub center: Vector,
+    pub half_size: Float,
+    pub mass: Float,
+    pub com: Vector,
+    pub first_child: Option<usize>,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Node {
+    pub fn contains(&self, pos: Vector) -> bool {
+        (pos - self.center).abs().max_element() <= self.half_size
+    }
+}
+
+pub struct QuadTree {
+    pub nodes: Vec<Node>,
+    // body indices, permuted so every node's bodies are contiguous
+    pub order: Vec<usize>,
+    // cells with at most this many bodies are not split any further
+    pub leaf_size: usize,
+}
+
+impl QuadTree {
+    pub fn build(pos: &[Vector], mass: &[Float]) -> QuadTree {
+        Self::with_leaf_size(pos, mass, 1)
+    }
+
+    pub fn with_leaf_size(pos: &[Vector], mass: &[Float], leaf_size: usize) -> QuadTree {
+        let (min, max) = pos.iter().fold(
+            (
+                Vector::splat(Float::INFINITY),
+                Vector::splat(Float::NEG_INFINITY),
+            ),
+            |(min, max), p| (min.min(*p), max.max(*p)),
+        );
+        let (center, half_size) = if pos.is_empty() {
+            (Vector::ZERO, 1.0)
+        } else {
+            (
+                (min + max) / 2.0,
+                ((max - min).max_element() / 2.0).max(Float::EPSILON),
+            )
+        };
+
+        let leaf_size = leaf_size.max(1);
+        let mut order: Vec<usize> = (0..pos.len()).collect();
+        let mut nodes = vec![Node {
+            center,
+            half_size,
+            mass: 0.0,
+            com: Vector::ZERO,
+            first_child: None,
+            start: 0,
+            end: pos.len(),
+        }];
+        subdivide(&mut nodes, 0, 0, &mut order, pos, mass, leaf_size);
+        QuadTree {
+            nodes,
+            order,
+            leaf_size,
+        }
+    }
+
+    // acceleration on body i, treating cells that look smaller than theta
+    // (cell width / distance) as a single mass at their centre of mass
+    pub fn accel_on(
+        &self,
+        gravity: &GravityModel,
+        i: usize,
+        pos: &[Vector],
+        mass: &[Float],
+        theta: Float,
+    ) -> Vector {
+        let p = pos[i];
+        let mut accel = Vector::ZERO;
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if node.mass == 0.0 {
+                continue;
+            }
+            match node.first_child {
+                None => {
+                    for &j in &self.order[node.start..node.end] {
+                        if j != i {
+                            accel += calc_point_accel(gravity, p, pos[j], mass[j]);
+                        }
+                    }
+                }
+                Some(first_child) => {
+                    let width = 2.0 * node.half_size;
+                    // a cell holding the body itself is always opened
+                    if !node.contains(p) && width < theta * p.distance(node.com) {
+                        accel += calc_point_accel(gravity, p, node.com, node.mass);
+                    } else {
+                        stack.extend(first_child..first_child + 4);
+                    }
+                }
+            }
+        }
+        accel
+    }
+}
+
+// Splits nodes[index], whose bodies are the slice `bodies` of the tree's order,
+// and recurses into the children. Children are appended depth first: the four
+// siblings together, then the whole subtree of each in turn.
+fn subdivide(
+    nodes: &mut Vec<Node>,
+    index: usize,
+    depth: usize,
+    bodies: &mut [usize],
+    pos: &[Vector],
+    mass: &[Float],
+    leaf_size: usize,
+) {
+    let Node {
+        center,
+        half_size,
+        start,
+        end,
+        ..
+    } = nodes[index];
+
+    if end - start <= leaf_size || depth >= MAX_DEPTH {
+        let (m, weighted) = bodies.iter().fold((0.0, Vector::ZERO), |(m, w), &i| {
+            (m + mass[i], w + pos[i] * mass[i])
+        });
+        nodes[index].mass = m;
+        nodes[index].com = if m > 0.0 { weighted / m } else { center };
+        return;
+    }
+
+    // split into quadrants: bottom left, bottom right, top left, top right
+    let split_y = partition(bodies, |i| pos[i].y < center.y);
+    let split_bl = partition(&mut bodies[..split_y], |i| pos[i].x < center.x);
+    let split_tl = partition(&mut bodies[split_y..], |i| pos[i].x < center.x) + split_y;
+    let bounds = [0, split_bl, split_y, split_tl, end - start];
+
+    let first_child = nodes.len();
+    let quarter = half_size / 2.0;
+    for q in 0..4 {
+        let offset = Vector::new(
+            if q % 2 == 0 { -quarter } else { quarter },
+            if q < 2 { -quarter } else { quarter },
+        );
+        nodes.push(Node {
+            center: center + offset,
+            half_size: quarter,
+            mass: 0.0,
+            com: Vector::ZERO,
+            first_child: None,
+            start: start + bounds[q],
+            end: start + bounds[q + 1],
+        });
+    }
+    nodes[index].first_child = Some(first_child);
+
+    let (rest, top_right) = bodies.split_at_mut(bounds[3]);
+    let (rest, top_left) = rest.split_at_mut(bounds[2]);
+    let (bottom_left, bottom_right) = rest.split_at_mut(bounds[1]);
+    let quadrants = [bottom_left, bottom_right, top_left, top_right];
+
+    #[cfg(feature = "parallel")]
+    if depth < PARALLEL_DEPTH && end - start >= PARALLEL_MIN_BODIES {
+        // build each quadrant in an arena of its own, then append them in the
+        // order the serial build would have, so the layout is identical
+        let children: [Node; 4] = std::array::from_fn(|q| nodes[first_child + q]);
+        let subtrees: Vec<Vec<Node>> = quadrants
+            .into_par_iter()
+            .zip(children)
+            .map(|(bodies, child)| {
+                let mut subtree = vec![child];
+                subdivide(&mut subtree, 0, depth + 1, bodies, pos, mass, leaf_size);
+                subtree
+            })
+            .collect();
+        for (q, subtree) in subtrees.into_iter().enumerate() {
+            // subtree[0] is the child itself, the rest land after the current end
+            let base = nodes.len() - 1;
+            let shift = |node: Node| Node {
+                first_child: node.first_child.map(|c| c + base),
+                ..node
+            };
+            nodes[first_child + q] = shift(subtree[0]);
+            nodes.extend(subtree[1..].iter().map(|&node| shift(node)));
+        }
+        sum_children(nodes, index, first_child);
+        return;
+    }
+
+    for (q, bodies) in quadrants.into_iter().enumerate() {
+        subdivide(
+            nodes,
+            first_child + q,
+            depth + 1,
+            bodies,
+            pos,
+            mass,
+            leaf_size,
+        );
+    }
+    sum_children(nodes, index, first_child);
+}
+
+fn sum_children(nodes: &mut [Node], index: usize, first_child: usize) {
+    let mut m = 0.0;
+    let mut weighted = Vector::ZERO;
+    for child in &nodes[first_child..first_child + 4] {
+        m += child.mass;
+        weighted += child.com * child.mass;
+    }
+    nodes[index].mass = m;
+    nodes[index].com = if m > 0.0 {
+        weighted / m
+    } else {
+        nodes[index].center
+    };
+}
+
+// moves the items matching pred to the front and returns how many there are
+fn partition(items: &mut [usize], pred: impl Fn(usize) -> bool) -> usize {
+    let mut split = 0;
+    for k in 0..items.len() {
+        if pred(items[k]) {
+            items.swap(split, k);
+            split += 1;
+        }
+    }
+    split
+}
+
+// Barnes-Hut tree code: O(n log n) gravity with the opening angle theta
+// trading accuracy for speed. theta = 0 opens every cell and matches the
+// direct sum.
+pub struct BarnesHut {
+    pub theta: Float,
+}
+
+impl BarnesHut {
+    pub fn new(theta: Float) -> BarnesHut {
+        BarnesHut { theta }
+    }
+}
+
+impl ForceSolver for BarnesHut {
+    fn accelerations(&self, gravity: &GravityModel, pos: &[Vector], mass: &[Float]) -> Vec<Vector> {
+        let tree = QuadTree::build(pos, mass);
+        map_bodies(pos.len(), |i| {
+            tree.accel_on(gravity, i, pos, mass, self.theta)
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        "Barnes-Hut"
+    }
+}
@@ -0,0 +1,33 @@
+use glam::Vec2;
+
+// Scalar and vector types of the simulation state. Everything runs in double
+// precision unless the "single-precision" feature is enabled; rendering
+// converts to f32 pixels only at draw time.
+#[cfg(not(feature = "single-precision"))]
+pub type Float = f64;
+#[cfg(not(feature = "single-precision"))]
+pub type Vector = glam::DVec2;
+
+#[cfg(feature = "single-precision")]
+pub type Float = f32;
+#[cfg(feature = "single-precision")]
+pub type Vector = glam::Vec2;
+
+// Rendering works in f32 whatever precision the simulation runs in
+#[cfg(not(feature = "single-precision"))]
+pub fn to_f32(x: Float) -> f32 {
+    x as f32
+}
+#[cfg(feature = "single-precision")]
+pub fn to_f32(x: Float) -> f32 {
+    x
+}
+
+#[cfg(not(feature = "single-precision"))]
+pub fn to_screen(v: Vector) -> Vec2 {
+    v.as_vec2()
+}
+#[cfg(feature = "single-precision")]
+pub fn to_screen(v: Vector) -> Vec2 {
+    v
+}
@@ -1,105 +1,519 @@
+use std::collections::{HashMap, HashSet};
 use std::time::Instant;
 
 use cosmic_text::{Attrs, Buffer, Color, FontSystem, Metrics, SwashCache};
-use glam::Vec2;
 use pixels::Pixels;
 
 use crate::{
-    physics::{calc_accel, check_collision},
-    planet::Planet,
+    bodies::{Bodies, BodyId},
+    collision::{
+        bounce, contact_groups, merge_groups, shatter_groups, specific_impact_energy,
+        swept_contacts, CollisionResponse, CollisionRules, FragmentEvent, Fragmentation,
+        MergeEvent,
+    },
+    diagnostics::{Diagnostics, Drift},
+    forces::{BodyState, Force},
+    integrator::{aarseth_timestep, Integrator, SemiImplicitEuler, TimestepMode},
+    orbit::KeplerReference,
+    physics::{calc_jerks, calc_pn_accelerations, DirectSum, ForceSolver, GravityModel},
+    planet::{Planet, PlanetColor},
+    scalar::{Float, Vector},
+    tides::{disrupt, disruptions, DisruptionEvent, TidalDisruption},
+    units::{UnitSystem, Viewport},
     HEIGHT, WIDTH,
 };
 
 pub struct PlanetSystem<'a> {
-    pub list: Vec<Planet<'a>>,
-    pub timestep: f32,
-    systeminfo: SystemInfo,
-    initial_energy: f32,
-    energy: f32,
+    pub bodies: Bodies<'a>,
+    pub timestep: Float,
+    integrator: Box<dyn Integrator>,
+    solver: Box<dyn ForceSolver>,
+    gravity: GravityModel,
+    forces: Vec<Box<dyn Force>>,
+    units: UnitSystem,
+    view: Viewport,
+    timestep_mode: TimestepMode,
+    step_log: Vec<Float>,
+    pub time: Float,
+    collision_rules: CollisionRules,
+    combine_volume: bool,
+    merge_log: Vec<MergeEvent>,
+    fragmentation: Option<Fragmentation>,
+    fragment_log: Vec<FragmentEvent>,
+    tides: Option<TidalDisruption>,
+    disruption_log: Vec<DisruptionEvent>,
+    // bodies made by a disruption, which are never disrupted themselves
+    fragments: HashSet<BodyId>,
+    kepler_reference: Option<KeplerReference>,
+    // created on first use, loading fonts is slow and headless runs never need it
+    systeminfo: Option<SystemInfo>,
+    // measured at the first update, drifts are reported against it
+    initial_diagnostics: Option<Diagnostics>,
 }
 
 impl<'a> PlanetSystem<'a> {
-    pub fn empty(timestep: f32) -> Self {
-        let systeminfo = SystemInfo::new();
+    pub fn empty(timestep: Float) -> Self {
         Self {
-            list: vec![],
+            bodies: Bodies::new(),
             timestep,
-            systeminfo,
-            initial_energy: 0.0,
-            energy: 0.0,
+            integrator: Box::new(SemiImplicitEuler),
+            solver: Box::new(DirectSum),
+            gravity: GravityModel::default(),
+            forces: vec![],
+            units: UnitSystem::Pixels,
+            view: Viewport::pixels(),
+            timestep_mode: TimestepMode::Fixed,
+            step_log: vec![],
+            time: 0.0,
+            collision_rules: CollisionRules::default(),
+            combine_volume: false,
+            merge_log: vec![],
+            fragmentation: None,
+            fragment_log: vec![],
+            tides: None,
+            disruption_log: vec![],
+            fragments: HashSet::new(),
+            kepler_reference: None,
+            systeminfo: None,
+            initial_diagnostics: None,
         }
     }
 
-    pub fn from_vec(timestep: f32, planet_list: Vec<Planet<'a>>) -> Self {
-        let systeminfo = SystemInfo::new();
-        let energy: f32 = planet_list.iter().fold(0.0, |mut t, p| {
-            t += p.calc_energy();
-            t
-        });
-
+    pub fn from_vec(timestep: Float, planet_list: Vec<Planet<'a>>) -> Self {
         Self {
-            list: planet_list,
-            timestep,
-            systeminfo,
-            initial_energy: energy,
-            energy: 0.0,
+            bodies: Bodies::from_planets(planet_list),
+            ..Self::empty(timestep)
         }
     }
 
-    pub fn add_planet(&mut self, planet: Planet<'a>) {
-        self.list.push(planet);
+    // the id stays with the body through merges and removals of other bodies
+    pub fn add_planet(&mut self, planet: Planet<'a>) -> BodyId {
+        self.bodies.push(planet)
+    }
+
+    // a massless body that feels the gravity of the others without pulling on
+    // them, and is drawn as a single pixel
+    pub fn add_test_particle(&mut self, name: &'a str, pos: Vector, vel: Vector) -> BodyId {
+        self.bodies
+            .push(Planet::test_particle(name, pos, vel, PlanetColor::white()))
+    }
+
+    pub fn index_of(&self, id: BodyId) -> Option<usize> {
+        self.bodies.index_of(id)
+    }
+
+    // a copy of body i, changes to it only take effect through set_planet
+    pub fn planet(&self, i: usize) -> Planet<'a> {
+        self.bodies.get(i)
+    }
+
+    pub fn set_planet(&mut self, i: usize, planet: Planet<'a>) {
+        self.bodies.set(i, planet);
+    }
+
+    pub fn planets(&self) -> impl Iterator<Item = Planet<'a>> + '_ {
+        self.bodies.iter()
+    }
+
+    pub fn set_integrator(&mut self, integrator: Box<dyn Integrator>) {
+        self.integrator = integrator;
+    }
+
+    pub fn integrator(&self) -> &dyn Integrator {
+        self.integrator.as_ref()
+    }
+
+    pub fn set_force_solver(&mut self, solver: Box<dyn ForceSolver>) {
+        self.solver = solver;
+    }
+
+    pub fn force_solver(&self) -> &dyn ForceSolver {
+        self.solver.as_ref()
+    }
+
+    pub fn set_gravity(&mut self, gravity: GravityModel) {
+        self.gravity = gravity;
+    }
+
+    pub fn gravity(&self) -> &GravityModel {
+        &self.gravity
+    }
+
+    // accelerates the bodies on top of their gravity from now on
+    pub fn add_force(&mut self, force: Box<dyn Force>) {
+        self.forces.push(force);
+    }
+
+    pub fn forces(&self) -> &[Box<dyn Force>] {
+        &self.forces
+    }
+
+    pub fn clear_forces(&mut self) {
+        self.forces.clear();
+    }
+
+    // declares what the numbers of this system mean and switches G to match,
+    // keeping the softening. Positions, masses, velocities and the timestep are
+    // taken to already be in these units.
+    pub fn set_units(&mut self, units: UnitSystem) {
+        self.units = units;
+        self.gravity.g = units.g();
+    }
+
+    pub fn units(&self) -> UnitSystem {
+        self.units
+    }
+
+    pub fn set_view(&mut self, view: Viewport) {
+        self.view = view;
+    }
+
+    pub fn view(&self) -> &Viewport {
+        &self.view
+    }
+
+    pub fn set_timestep_mode(&mut self, mode: TimestepMode) {
+        mode.validate();
+        self.timestep_mode = mode;
+    }
+
+    pub fn timestep_mode(&self) -> TimestepMode {
+        self.timestep_mode
+    }
+
+    // the steps the integrator actually took during the last update, they always
+    // add up to timestep so rendering still advances at a constant rate
+    pub fn last_steps(&self) -> &[Float] {
+        &self.step_log
+    }
+
+    // what touching bodies do, for every pair without a response of its own
+    pub fn set_collision_response(&mut self, response: CollisionResponse) {
+        self.collision_rules.set_default(response);
+    }
+
+    // what bodies a and b do when they touch, whatever the global response
+    pub fn set_pair_response(&mut self, a: BodyId, b: BodyId, response: CollisionResponse) {
+        self.collision_rules.set_pair(a, b, response);
+    }
+
+    pub fn collision_rules(&self) -> &CollisionRules {
+        &self.collision_rules
+    }
+
+    // whether merged bodies take the radius of a sphere holding all their
+    // volume, rather than keeping the radius of the most massive one
+    pub fn set_combine_volume(&mut self, combine: bool) {
+        self.combine_volume = combine;
+    }
+
+    pub fn combine_volume(&self) -> bool {
+        self.combine_volume
+    }
+
+    // the merges that happened during the last update
+    pub fn last_merges(&self) -> &[MergeEvent] {
+        &self.merge_log
+    }
+
+    // impacts energetic enough shatter the bodies instead of merging them,
+    // none keeps every merge a merge
+    pub fn set_fragmentation(&mut self, fragmentation: Option<Fragmentation>) {
+        self.fragmentation = fragmentation;
+    }
+
+    pub fn fragmentation(&self) -> Option<&Fragmentation> {
+        self.fragmentation.as_ref()
+    }
+
+    // the shatterings that happened during the last update
+    pub fn last_fragmentations(&self) -> &[FragmentEvent] {
+        &self.fragment_log
+    }
+
+    // bodies passing within the Roche limit of a much heavier one are torn
+    // apart, none leaves every body whole
+    pub fn set_tidal_disruption(&mut self, tides: Option<TidalDisruption>) {
+        self.tides = tides;
+    }
+
+    pub fn tidal_disruption(&self) -> Option<&TidalDisruption> {
+        self.tides.as_ref()
+    }
+
+    // the disruptions that happened during the last update
+    pub fn last_disruptions(&self) -> &[DisruptionEvent] {
+        &self.disruption_log
+    }
+
+    // from now on, follow how far the orbit of body about primary strays from
+    // the analytic two-body orbit they are on at this moment, recorded after
+    // every update. Meant for checking integrators on systems where the two
+    // bodies really are alone. False if either body doesn't exist.
+    pub fn compare_with_kepler(&mut self, body: BodyId, primary: BodyId) -> bool {
+        let (Some(i), Some(j)) = (self.index_of(body), self.index_of(primary)) else {
+            return false;
+        };
+        self.kepler_reference = Some(KeplerReference::new(
+            &self.gravity,
+            body,
+            &self.planet(i),
+            primary,
+            &self.planet(j),
+            self.time,
+        ));
+        true
+    }
+
+    pub fn stop_kepler_comparison(&mut self) {
+        self.kepler_reference = None;
+    }
+
+    pub fn kepler_reference(&self) -> Option<&KeplerReference> {
+        self.kepler_reference.as_ref()
+    }
+
+    // (time, position error) after every update since compare_with_kepler
+    pub fn kepler_errors(&self) -> &[(Float, Float)] {
+        self.kepler_reference
+            .as_ref()
+            .map_or(&[], |reference| reference.errors())
     }
 
     pub fn update_system(&mut self) {
-        let mut accel_list: Vec<Vec2> = vec![];
-        let mut merge_list: Vec<(usize, usize)> = vec![];
-        for s in self.list.iter() {
-            let mut accel = Vec2::new(0.0, 0.0);
-            for p in self.list.iter() {
-                if s == p {
-                    continue;
+        if self.initial_diagnostics.is_none() {
+            self.initial_diagnostics = Some(self.diagnostics());
+        }
+        self.step_log.clear();
+        self.merge_log.clear();
+        self.fragment_log.clear();
+        self.disruption_log.clear();
+        for collided in self.bodies.in_collision.iter_mut() {
+            *collided = false;
+        }
+        let mut elapsed = 0.0;
+        while elapsed < self.timestep {
+            let start = self.bodies.pos.clone();
+            let dt = self.step(self.timestep - elapsed);
+            self.resolve_collisions(&start, self.time + elapsed, dt);
+            self.resolve_tides(self.time + elapsed + dt);
+            self.step_log.push(dt);
+            elapsed += dt;
+        }
+        self.time += self.timestep;
+        if let Some(reference) = self.kepler_reference.as_mut() {
+            if let (Some(i), Some(j)) = (
+                self.bodies.index_of(reference.body),
+                self.bodies.index_of(reference.primary),
+            ) {
+                reference.record(self.time, &self.bodies.get(i), &self.bodies.get(j));
+            }
+        }
+    }
+
+    // one integrator step of at most remaining, returns the step taken
+    fn step(&mut self, remaining: Float) -> Float {
+        let Bodies {
+            id,
+            pos,
+            vel,
+            accel,
+            mass,
+            radius,
+            ..
+        } = &mut self.bodies;
+        let solver = self.solver.as_ref();
+        let gravity = &self.gravity;
+        let forces = &self.forces;
+        // test particles feel the massive bodies but leave the solver's sum
+        // over sources, so they cost O(massive) each instead of O(n)
+        let (massive, particles): (Vec<usize>, Vec<usize>) =
+            (0..mass.len()).partition(|&i| mass[i] != 0.0);
+        let gravity_fn = |pos: &[Vector]| {
+            if particles.is_empty() {
+                return solver.accelerations(gravity, pos, mass);
+            }
+            let source_pos: Vec<Vector> = massive.iter().map(|&i| pos[i]).collect();
+            let source_mass: Vec<Float> = massive.iter().map(|&i| mass[i]).collect();
+            let points: Vec<Vector> = particles.iter().map(|&i| pos[i]).collect();
+            let mut accel = vec![Vector::ZERO; pos.len()];
+            let on_massive = solver.accelerations(gravity, &source_pos, &source_mass);
+            for (&i, a) in massive.iter().zip(on_massive) {
+                accel[i] = a;
+            }
+            let on_particles = solver.field(gravity, &points, &source_pos, &source_mass);
+            for (&i, a) in particles.iter().zip(on_particles) {
+                accel[i] = a;
+            }
+            accel
+        };
+        let accel_fn = |pos: &[Vector], vel: &[Vector]| {
+            let mut accel = gravity_fn(pos);
+            if gravity.speed_of_light.is_some() {
+                let correction = calc_pn_accelerations(gravity, pos, vel, mass);
+                for (a, c) in accel.iter_mut().zip(correction) {
+                    *a += c;
                 }
-                if check_collision(&s, &p) {
-                    if s.mass >= p.mass {
-                        //p gets merged into s
-                        let s_index = self.list.iter().position(|x| *x == *s);
-                        let p_index = self.list.iter().position(|x| *x == *p);
-                        if (s_index != None) & (p_index != None) {
-                            if !merge_list
-                                .iter()
-                                .any(|(_, b)| (*b == p_index.unwrap()) | (*b == s_index.unwrap()))
-                            {
-                                merge_list.push((s_index.unwrap(), p_index.unwrap()));
-                            }
-                        }
-                    }
+            }
+            let state = BodyState {
+                id,
+                pos,
+                vel,
+                mass,
+                radius,
+            };
+            for force in forces.iter() {
+                force.add_accelerations(&state, &mut accel);
+            }
+            accel
+        };
+        let dt = match self.timestep_mode {
+            TimestepMode::Fixed => remaining,
+            TimestepMode::Adaptive {
+                eta,
+                min_step,
+                max_step,
+            } => {
+                let current = gravity_fn(pos);
+                let jerk = calc_jerks(gravity, pos, vel, mass);
+                let dt = aarseth_timestep(&current, &jerk, eta);
+                // nothing constrains the step
+                if dt.is_finite() && dt > 0.0 {
+                    dt.clamp(min_step, max_step).min(remaining)
+                } else {
+                    max_step.min(remaining)
+                }
+            }
+        };
+        self.integrator.prepare(gravity, mass);
+        *accel = self.integrator.step(pos, vel, dt, &accel_fn);
+        dt
+    }
+
+    // Resolves the collisions of the step of length dt that began at time
+    // step_start with the bodies at start. Bodies are taken to move in
+    // straight lines during the step, so fast ones cannot pass through each
+    // other unseen. Every body that hit something goes back to its first
+    // impact, the touching pairs that bounce bounce there, then every group of
+    // bodies joined by pairs that merge merges. Bodies in a merging pair
+    // energetic enough to shatter shatter with everything they touch instead.
+    // Whatever comes out of a collision then coasts for the rest of the step.
+    fn resolve_collisions(&mut self, start: &[Vector], step_start: Float, dt: Float) {
+        // test particles pass through each other
+        let mass = &self.bodies.mass;
+        let impacts: Vec<(usize, usize, Float)> =
+            swept_contacts(start, &self.bodies.pos, &self.bodies.radius)
+                .into_iter()
+                .filter(|&(i, j, _)| mass[i] != 0.0 || mass[j] != 0.0)
+                .collect();
+        if impacts.is_empty() {
+            return;
+        }
+        let mut impact = vec![Float::INFINITY; self.bodies.len()];
+        for &(i, j, t) in impacts.iter() {
+            impact[i] = impact[i].min(t);
+            impact[j] = impact[j].min(t);
+        }
+        // how far into the step each body that collided picks up again, by id
+        // since merging and shattering move the bodies around
+        let mut resume: HashMap<BodyId, Float> = HashMap::new();
+        for i in (0..self.bodies.len()).filter(|&i| impact[i].is_finite()) {
+            self.bodies.pos[i] = start[i] + impact[i] * (self.bodies.pos[i] - start[i]);
+            self.bodies.in_collision[i] = true;
+            resume.insert(self.bodies.id[i], impact[i]);
+        }
+
+        let mut merging = vec![];
+        for &(i, j, _) in impacts.iter() {
+            let response = self
+                .collision_rules
+                .response(self.bodies.id[i], self.bodies.id[j]);
+            match response.restitution() {
+                Some(restitution) => bounce(&mut self.bodies, i, j, restitution),
+                None => merging.push((i, j)),
+            }
+        }
+        let mut shattering = vec![];
+        if let Some(fragmentation) = self.fragmentation {
+            let mut shatters = vec![false; self.bodies.len()];
+            for &(i, j) in merging.iter() {
+                if specific_impact_energy(&self.bodies, i, j) > fragmentation.threshold {
+                    shatters[i] = true;
+                    shatters[j] = true;
                 }
-                accel += calc_accel(&s, &p);
             }
-            accel_list.push(accel);
+            (shattering, merging) = merging
+                .into_iter()
+                .partition(|&(i, j)| shatters[i] || shatters[j]);
+        }
+        // held by id, merging moves the bodies around
+        let shattering: Vec<Vec<BodyId>> = contact_groups(self.bodies.len(), &shattering)
+            .iter()
+            .map(|group| group.iter().map(|&i| self.bodies.id[i]).collect())
+            .collect();
+
+        let groups = contact_groups(self.bodies.len(), &merging);
+        let mut merges = merge_groups(&mut self.bodies, &groups, self.combine_volume, step_start);
+        for event in merges.iter_mut() {
+            let t = event
+                .absorbed
+                .iter()
+                .fold(resume[&event.survivor], |t, id| t.min(resume[id]));
+            resume.insert(event.survivor, t);
+            event.time = step_start + t * dt;
+            self.collision_rules.forget(&event.absorbed);
         }
-        for i in 0..self.list.len() {
-            self.list[i].update(self.timestep, accel_list[i]);
+        self.merge_log.append(&mut merges);
+        if let Some(fragmentation) = self.fragmentation {
+            let groups: Vec<Vec<usize>> = shattering
+                .iter()
+                .map(|group| {
+                    group
+                        .iter()
+                        .filter_map(|&id| self.bodies.index_of(id))
+                        .collect()
+                })
+                .collect();
+            let mut shatters =
+                shatter_groups(&mut self.bodies, &groups, &fragmentation, step_start);
+            for event in shatters.iter_mut() {
+                let t = event
+                    .parents
+                    .iter()
+                    .fold(Float::INFINITY, |t, id| t.min(resume[id]));
+                for &fragment in event.fragments.iter() {
+                    resume.insert(fragment, t);
+                }
+                event.time = step_start + t * dt;
+                self.collision_rules.forget(&event.parents);
+            }
+            self.fragment_log.append(&mut shatters);
         }
-        for pair in merge_list.iter() {
-            let mass_a = self.list[pair.0].mass;
-            let vel_a = self.list[pair.0].vel;
-            let mass_b = self.list[pair.1].mass;
-            let vel_b = self.list[pair.1].vel;
 
-            let init_momentum = mass_a * vel_a + mass_b * vel_b;
-            let final_mass = mass_a + mass_b;
-            let final_velocity = init_momentum / final_mass;
+        for (id, t) in resume {
+            if let Some(i) = self.bodies.index_of(id) {
+                self.bodies.pos[i] += (1.0 - t) * dt * self.bodies.vel[i];
+                self.bodies.in_collision[i] = true;
+            }
+        }
+    }
 
-            self.list.remove(pair.1);
-            self.list[pair.0].mass += final_mass;
-            self.list[pair.0].vel += final_velocity;
+    fn resolve_tides(&mut self, time: Float) {
+        if let Some(tides) = self.tides {
+            let found = disruptions(&self.bodies, &tides, &self.fragments);
+            let mut events = disrupt(&mut self.bodies, &found, &tides, time);
+            for event in events.iter() {
+                self.collision_rules.forget(&[event.body]);
+                self.fragments.remove(&event.body);
+                self.fragments.extend(event.fragments.iter().copied());
+            }
+            self.disruption_log.append(&mut events);
         }
     }
 
     pub fn render_system(&mut self, pixels: &mut Pixels) {
-        for p in self.list.iter() {
-            p.render(pixels);
+        for p in self.bodies.iter() {
+            p.render(pixels, &self.view);
         }
     }
 
@@ -110,21 +524,82 @@ impl<'a> PlanetSystem<'a> {
     }
 
     pub fn print_info(&mut self, pixels: &mut Pixels) {
-        let energy = self.calc_total_energy();
-        let mut energy_string = format!("Total Energy of the system: {}J\n", energy).to_string();
-        let change_energy_string = format!(
-            "Change in energy of the system: {}J\n",
-            self.initial_energy - energy
-        );
-        energy_string += change_energy_string.as_str();
-        self.systeminfo.render_info(pixels, energy_string.as_str());
+        let now = self.diagnostics();
+        let drift = self.drift_from(&now);
+        let unit = self.units.energy_unit();
+        let mut info = format!("Total energy: {:.6e} {}", now.energy(), unit);
+        if let Some(drift) = drift {
+            info += format!(" (drift {:.2e})", drift.energy).as_str();
+        }
+        info += format!(
+            "\nMomentum: ({:.3e}, {:.3e})",
+            now.momentum.x, now.momentum.y
+        )
+        .as_str();
+        if let Some(drift) = drift {
+            info += format!(" (drift {:.2e})", drift.momentum).as_str();
+        }
+        info += format!("\nAngular momentum: {:.6e}", now.angular_momentum).as_str();
+        if let Some(drift) = drift {
+            info += format!(" (drift {:.2e})", drift.angular_momentum).as_str();
+        }
+        info += format!(
+            "\nCentre of mass: ({:.3}, {:.3})\n",
+            now.center_of_mass.x, now.center_of_mass.y
+        )
+        .as_str();
+        info += format!("Time: {:.1} {}\n", self.time, self.units.time_unit()).as_str();
+        if let Some(&(_, error)) = self.kepler_errors().last() {
+            info += format!("Kepler position error: {:.3e}\n", error).as_str();
+        }
+        if let TimestepMode::Adaptive { .. } = self.timestep_mode {
+            let smallest = self.step_log.iter().fold(self.timestep, |a, &b| a.min(b));
+            info += format!(
+                "Steps this frame: {} (smallest {:.2e})\n",
+                self.step_log.len(),
+                smallest
+            )
+            .as_str();
+        }
+        self.systeminfo
+            .get_or_insert_with(SystemInfo::new)
+            .render_info(pixels, info.as_str());
+    }
+
+    // energy, momenta and centre of mass of the system as it is now, O(n^2) in
+    // the number of bodies for the potential energy
+    pub fn diagnostics(&self) -> Diagnostics {
+        Diagnostics::measure(
+            &self.gravity,
+            &self.bodies.pos,
+            &self.bodies.vel,
+            &self.bodies.mass,
+        )
+    }
+
+    pub fn initial_diagnostics(&self) -> Option<&Diagnostics> {
+        self.initial_diagnostics.as_ref()
+    }
+
+    // relative change of the conserved quantities since the first update, none
+    // before the system has been updated
+    pub fn drift(&self) -> Option<Drift> {
+        self.drift_from(&self.diagnostics())
+    }
+
+    fn drift_from(&self, now: &Diagnostics) -> Option<Drift> {
+        self.initial_diagnostics
+            .as_ref()
+            .map(|initial| now.drift(initial))
+    }
+
+    // measure drifts from the current state from now on, e.g. after adding bodies
+    pub fn reset_diagnostics(&mut self) {
+        self.initial_diagnostics = Some(self.diagnostics());
     }
 
-    pub fn calc_total_energy(&self) -> f32 {
-        self.list.iter().fold(0.0, |mut t, p| {
-            t += p.calc_energy();
-            t
-        })
+    pub fn calc_total_energy(&self) -> Float {
+        self.diagnostics().energy()
     }
 }
 
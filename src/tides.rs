@@ -0,0 +1,112 @@
+use std::collections::HashSet;
+
+use crate::bodies::{Bodies, BodyId};
+use crate::collision::fragment_ring;
+use crate::physics::roche_limit;
+use crate::scalar::Float;
+
+// Coefficients of the Roche limit for a satellite held together by its rigidity
+// alone and for one that deforms like a fluid
+pub const RIGID: Float = 1.26;
+pub const FLUID: Float = 2.44;
+
+// Bodies passing within the Roche limit of a much more massive one are torn
+// into a ring of fragments
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct TidalDisruption {
+    // RIGID, FLUID or anything between
+    pub coefficient: Float,
+    // only bodies at least this many times heavier than a body can disrupt it
+    pub mass_ratio: Float,
+    // bodies lighter than this hold together by their own strength
+    pub min_mass: Float,
+    // how many equal fragments a disrupted body becomes, at least 2
+    pub fragments: usize,
+}
+
+// One disruption: body is gone and the fragments have taken its place
+#[derive(Clone, PartialEq, Debug)]
+pub struct DisruptionEvent {
+    pub time: Float,
+    pub body: BodyId,
+    pub primary: BodyId,
+    pub fragments: Vec<BodyId>,
+    pub distance: Float,
+    pub roche_limit: Float,
+}
+
+// Every body inside the Roche limit of a primary heavy enough to disrupt it,
+// as (body, primary) in ascending order of body. A body inside the limits of
+// several primaries goes with the one it is deepest inside. Fragments have
+// the density, and so the Roche limit, of their parent and start out inside
+// it, so the ones in fragments are left alone rather than torn apart again
+// every step. O(n^2).
+pub fn disruptions(
+    bodies: &Bodies,
+    tides: &TidalDisruption,
+    fragments: &HashSet<BodyId>,
+) -> Vec<(usize, usize)> {
+    let mut found = vec![];
+    for i in (0..bodies.len())
+        .filter(|&i| bodies.mass[i] >= tides.min_mass && !fragments.contains(&bodies.id[i]))
+    {
+        let satellite = bodies.get(i);
+        if satellite.mass <= 0.0 {
+            continue;
+        }
+        let mut deepest: Option<(usize, Float)> = None;
+        for j in (0..bodies.len()).filter(|&j| bodies.mass[j] >= tides.mass_ratio * satellite.mass)
+        {
+            if j == i {
+                continue;
+            }
+            let depth = satellite.pos.distance(bodies.pos[j])
+                / roche_limit(&satellite, &bodies.get(j), tides.coefficient);
+            let deeper = match deepest {
+                Some((_, d)) => depth < d,
+                None => true,
+            };
+            if depth < 1.0 && deeper {
+                deepest = Some((j, depth));
+            }
+        }
+        if let Some((j, _)) = deepest {
+            found.push((i, j));
+        }
+    }
+    found
+}
+
+// Tears every body in found apart into equal fragments on a ring about where
+// it was, all moving with its velocity, so mass, momentum, angular momentum,
+// volume and the centre of mass are conserved. The fragments take the name
+// and colour of their parent and are added after every other body.
+pub fn disrupt(
+    bodies: &mut Bodies,
+    found: &[(usize, usize)],
+    tides: &TidalDisruption,
+    time: Float,
+) -> Vec<DisruptionEvent> {
+    let count = tides.fragments.max(2);
+    let mut keep = vec![true; bodies.len()];
+    let mut events = vec![];
+    for &(i, j) in found {
+        let parent = bodies.get(i);
+        let towards = bodies.pos[j] - parent.pos;
+        let fragments = fragment_ring(bodies, &parent, count, towards.y.atan2(towards.x), 0.0);
+        keep.resize(bodies.len(), true);
+        keep[i] = false;
+        events.push(DisruptionEvent {
+            time,
+            body: bodies.id[i],
+            primary: bodies.id[j],
+            fragments,
+            distance: towards.length(),
+            roche_limit: roche_limit(&parent, &bodies.get(j), tides.coefficient),
+        });
+    }
+    if !events.is_empty() {
+        bodies.retain(&keep);
+    }
+    events
+}
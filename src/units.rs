@@ -0,0 +1,134 @@
+use glam::Vec2;
+
+use crate::physics::{GravityModel, Softening};
+use crate::scalar::{to_f32, to_screen, Float, Vector};
+use crate::{HEIGHT, WIDTH};
+
+// exact, metres
+pub const AU: Float = 1.495_978_707e11_f64 as Float;
+// GM_sun / G, so G * SOLAR_MASS is the measured heliocentric constant
+pub const SOLAR_MASS: Float = 1.988_41e30;
+pub const DAY: Float = 86_400.0;
+// Julian year
+pub const YEAR: Float = 365.25 * DAY;
+// CODATA 2018
+pub const G_SI: Float = 6.674_30e-11;
+// exact, metres per second
+pub const C_SI: Float = 299_792_458.0;
+
+// The units positions, masses and times of a system are given in. Each one
+// fixes the gravitational constant.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum UnitSystem {
+    // the original setup: pixels, arbitrary masses and G = 6.6e-11
+    Pixels,
+    // metres, kilograms and seconds
+    Si,
+    // astronomical units, solar masses and days
+    AstronomicalDays,
+    // astronomical units, solar masses and Julian years, G is close to 4 pi^2
+    AstronomicalYears,
+    // Henon units: G = 1, and by convention total mass 1 and total energy -1/4
+    NBody,
+}
+
+impl UnitSystem {
+    pub fn g(&self) -> Float {
+        match self {
+            UnitSystem::Pixels => GravityModel::default().g,
+            UnitSystem::NBody => 1.0,
+            _ => {
+                let (length, mass, time) = self.si_scales().unwrap();
+                G_SI * mass * time * time / (length * length * length)
+            }
+        }
+    }
+
+    // the unsoftened law of gravity in these units
+    pub fn gravity(&self) -> GravityModel {
+        GravityModel::new(self.g(), Softening::None)
+    }
+
+    // metres, kilograms and seconds per unit of length, mass and time, for the
+    // systems tied to physical units
+    pub fn si_scales(&self) -> Option<(Float, Float, Float)> {
+        match self {
+            UnitSystem::Si => Some((1.0, 1.0, 1.0)),
+            UnitSystem::AstronomicalDays => Some((AU, SOLAR_MASS, DAY)),
+            UnitSystem::AstronomicalYears => Some((AU, SOLAR_MASS, YEAR)),
+            UnitSystem::Pixels | UnitSystem::NBody => None,
+        }
+    }
+
+    // for GravityModel::post_newtonian, none in the systems with no physical
+    // length and time
+    pub fn speed_of_light(&self) -> Option<Float> {
+        self.si_scales()
+            .map(|(length, _, time)| C_SI * time / length)
+    }
+
+    pub fn time_unit(&self) -> &'static str {
+        match self {
+            UnitSystem::Si => "s",
+            UnitSystem::AstronomicalDays => "days",
+            UnitSystem::AstronomicalYears => "years",
+            // like lengths, the times of pixel systems are no physical unit
+            UnitSystem::Pixels | UnitSystem::NBody => "",
+        }
+    }
+
+    pub fn energy_unit(&self) -> &'static str {
+        match self {
+            UnitSystem::Si => "J",
+            UnitSystem::AstronomicalDays => "Msun AU^2/day^2",
+            UnitSystem::AstronomicalYears => "Msun AU^2/yr^2",
+            // pixels are no physical length
+            UnitSystem::Pixels | UnitSystem::NBody => "",
+        }
+    }
+}
+
+// Maps world coordinates onto the pixel buffer: screen = world * scale + offset.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Viewport {
+    // pixels per unit of length
+    pub scale: Float,
+    pub offset: Vec2,
+    // bodies smaller than this many pixels are still drawn this big
+    pub min_radius: f32,
+}
+
+impl Viewport {
+    // one pixel per unit of length, what every system used before units existed
+    pub fn pixels() -> Viewport {
+        Viewport {
+            scale: 1.0,
+            offset: Vec2::ZERO,
+            min_radius: 1.0,
+        }
+    }
+
+    // world point center in the middle of the window, scale pixels per unit of length
+    pub fn centered(center: Vector, scale: Float) -> Viewport {
+        let middle = Vec2::new(WIDTH as f32 / 2.0, HEIGHT as f32 / 2.0);
+        Viewport {
+            scale,
+            offset: middle - to_screen(center * scale),
+            min_radius: 2.0,
+        }
+    }
+
+    pub fn to_screen(&self, pos: Vector) -> Vec2 {
+        to_screen(pos * self.scale) + self.offset
+    }
+
+    pub fn to_screen_radius(&self, radius: Float) -> f32 {
+        to_f32(radius * self.scale).max(self.min_radius)
+    }
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self::pixels()
+    }
+}
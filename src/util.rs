@@ -1,7 +1,9 @@
 use pixels::{Pixels, SurfaceTexture};
 use winit::window::Window;
 
-pub const DEFAULT_TIMESTEP: f32 = 0.1;
+use crate::scalar::Float;
+
+pub const DEFAULT_TIMESTEP: Float = 0.1;
 pub const WIDTH: usize = 1200;
 pub const HEIGHT: usize = 800;
 pub const BRIGHTNESS_THRESHOLD: u8 = 100;
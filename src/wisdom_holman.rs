@@ -0,0 +1,170 @@
+use crate::integrator::{AccelFn, Integrator};
+use crate::orbit::propagate;
+use crate::physics::GravityModel;
+use crate::scalar::{Float, Vector};
+
+// Wisdom-Holman map in democratic heliocentric coordinates (Duncan, Levison &
+// Lee 1998) for systems with one dominant body, the star, which is whichever
+// body is heaviest. Every other body drifts along its exact Kepler orbit about
+// the star and only the small pulls between them are kicks, so the error
+// scales with the planet to star mass ratio and steps can be a small fraction
+// of the shortest orbit where leapfrog needs hundreds. Close encounters
+// between planets break that assumption. Forces the system adds on top of
+// gravity act on the planets as part of the kicks but not on the star, and
+// the Kepler drifts are unsoftened.
+pub struct WisdomHolman {
+    gravity: GravityModel,
+    mass: Vec<Float>,
+}
+
+impl WisdomHolman {
+    pub fn new() -> WisdomHolman {
+        WisdomHolman {
+            gravity: GravityModel::default(),
+            mass: vec![],
+        }
+    }
+}
+
+impl Default for WisdomHolman {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Positions relative to the star and velocities relative to the centre of mass,
+// along with the centre of mass itself
+struct Heliocentric {
+    star: usize,
+    q: Vec<Vector>,
+    u: Vec<Vector>,
+    center: Vector,
+    center_vel: Vector,
+}
+
+impl WisdomHolman {
+    fn to_heliocentric(&self, pos: &[Vector], vel: &[Vector], star: usize) -> Heliocentric {
+        let total: Float = self.mass.iter().sum();
+        let mut center = Vector::ZERO;
+        let mut center_vel = Vector::ZERO;
+        for ((&p, &v), &m) in pos.iter().zip(vel).zip(&self.mass) {
+            center += m * p;
+            center_vel += m * v;
+        }
+        center /= total;
+        center_vel /= total;
+        Heliocentric {
+            star,
+            q: pos.iter().map(|&p| p - pos[star]).collect(),
+            u: vel.iter().map(|&v| v - center_vel).collect(),
+            center,
+            center_vel,
+        }
+    }
+
+    fn to_inertial(&self, h: &Heliocentric, pos: &mut [Vector], vel: &mut [Vector]) {
+        let total: Float = self.mass.iter().sum();
+        let mut weighted_q = Vector::ZERO;
+        let mut momentum = Vector::ZERO;
+        for i in (0..pos.len()).filter(|&i| i != h.star) {
+            weighted_q += self.mass[i] * h.q[i];
+            momentum += self.mass[i] * h.u[i];
+        }
+        let star_pos = h.center - weighted_q / total;
+        for i in 0..pos.len() {
+            pos[i] = star_pos + h.q[i];
+            vel[i] = h.center_vel + h.u[i];
+        }
+        pos[h.star] = star_pos;
+        vel[h.star] = h.center_vel - momentum / self.mass[h.star];
+    }
+
+    // the pulls of the planets on each other, everything accel gives a planet
+    // but the star's own pull. Returns the full accelerations as well.
+    fn interaction_kick(
+        &self,
+        h: &mut Heliocentric,
+        accel: &AccelFn,
+        dt: Float,
+        pos: &mut [Vector],
+        vel: &mut [Vector],
+    ) -> Vec<Vector> {
+        self.to_inertial(h, pos, vel);
+        let a = accel(pos, vel);
+        let gm = self.gravity.g * self.mass[h.star];
+        for i in (0..pos.len()).filter(|&i| i != h.star) {
+            let to_star = pos[h.star] - pos[i];
+            let kepler = gm * self.gravity.force_factor(to_star.length_squared()) * to_star;
+            h.u[i] += (a[i] - kepler) * dt;
+        }
+        a
+    }
+
+    // the star moving to keep the centre of mass fixed
+    fn jump(&self, h: &mut Heliocentric, dt: Float) {
+        let mut momentum = Vector::ZERO;
+        for i in (0..h.q.len()).filter(|&i| i != h.star) {
+            momentum += self.mass[i] * h.u[i];
+        }
+        let shift = momentum / self.mass[h.star] * dt;
+        for i in (0..h.q.len()).filter(|&i| i != h.star) {
+            h.q[i] += shift;
+        }
+    }
+
+    fn kepler_drift(&self, h: &mut Heliocentric, dt: Float) {
+        let mu = self.gravity.g * self.mass[h.star];
+        for i in (0..h.q.len()).filter(|&i| i != h.star) {
+            (h.q[i], h.u[i]) = propagate(h.q[i], h.u[i], mu, dt);
+        }
+    }
+}
+
+impl Integrator for WisdomHolman {
+    fn prepare(&mut self, gravity: &GravityModel, mass: &[Float]) {
+        self.gravity = *gravity;
+        self.mass.clear();
+        self.mass.extend_from_slice(mass);
+    }
+
+    fn step(
+        &mut self,
+        pos: &mut [Vector],
+        vel: &mut [Vector],
+        timestep: Float,
+        accel: &AccelFn,
+    ) -> Vec<Vector> {
+        assert_eq!(
+            self.mass.len(),
+            pos.len(),
+            "Wisdom-Holman needs the masses, call prepare before every step"
+        );
+        let star = (0..pos.len()).fold(0, |best, i| {
+            if self.mass[i] > self.mass[best] {
+                i
+            } else {
+                best
+            }
+        });
+        if pos.len() < 2 || self.mass[star] <= 0.0 {
+            // nothing to orbit
+            for (p, v) in pos.iter_mut().zip(vel.iter()) {
+                *p += *v * timestep;
+            }
+            return accel(pos, vel);
+        }
+        let mut h = self.to_heliocentric(pos, vel, star);
+        self.interaction_kick(&mut h, accel, timestep / 2.0, pos, vel);
+        self.jump(&mut h, timestep / 2.0);
+        self.kepler_drift(&mut h, timestep);
+        self.jump(&mut h, timestep / 2.0);
+        h.center += h.center_vel * timestep;
+        let a = self.interaction_kick(&mut h, accel, timestep / 2.0, pos, vel);
+        self.to_inertial(&h, pos, vel);
+        a
+    }
+
+    fn name(&self) -> &'static str {
+        "Wisdom-Holman"
+    }
+}
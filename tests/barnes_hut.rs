@@ -0,0 +1,16 @@
+// the tolerances below are for double precision, f32 rounding alone exceeds them
+#![cfg(not(feature = "single-precision"))]
+
+mod common;
+
+use common::{random_disk, rms_relative_error};
+use Wallfacer::quadtree::BarnesHut;
+
+#[test]
+fn barnes_hut_matches_direct_sum() {
+    let (pos, mass) = random_disk(2000);
+    // opening every cell is the direct sum in a different order
+    assert!(rms_relative_error(&BarnesHut::new(0.0), &pos, &mass) < 1e-10);
+    let error = rms_relative_error(&BarnesHut::new(0.5), &pos, &mass);
+    assert!(error < 1e-3, "relative error {}", error);
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use common::random_disk;
+use Wallfacer::{
+    bodies::Bodies,
+    physics::{calc_accelerations, calc_point_accel, GravityModel},
+    planet::{Planet, PlanetColor},
+    scalar::{Float, Vector},
+};
+
+#[test]
+fn planets_round_trip_through_the_store() {
+    let planets = [
+        Planet::new(
+            "Sun",
+            Vector::new(600.0, 400.0),
+            10.0,
+            Vector::ZERO,
+            1e10,
+            PlanetColor::white(),
+        ),
+        Planet::new(
+            "Earth",
+            Vector::new(700.0, 400.0),
+            5.0,
+            Vector::new(0.0, 0.08),
+            1e5,
+            PlanetColor::blue(),
+        ),
+        Planet::new(
+            "Moon",
+            Vector::new(710.0, 400.0),
+            2.0,
+            Vector::new(0.0, 0.09),
+            1e2,
+            PlanetColor::white(),
+        ),
+    ];
+    let mut bodies = Bodies::from_planets(planets);
+    assert_eq!(bodies.len(), 3);
+    assert!(bodies.iter().eq(planets));
+
+    let removed = bodies.remove(1);
+    assert!(removed == planets[1]);
+    assert!(bodies.get(1) == planets[2]);
+    assert_eq!(bodies.mass, vec![1e10, 1e2]);
+}
+
+#[test]
+fn vectorised_direct_sum_matches_pairwise_sum() {
+    // an odd count so the last block is padded
+    let (pos, mass) = random_disk(1001);
+    let gravity = GravityModel::default();
+    let accel = calc_accelerations(&gravity, &pos, &mass);
+    for (i, a) in accel.iter().enumerate() {
+        let expected: Vector = (0..pos.len())
+            .filter(|&j| j != i)
+            .map(|j| calc_point_accel(&gravity, pos[i], pos[j], mass[j]))
+            .sum();
+        let tolerance = 1e3 * Float::EPSILON * expected.length();
+        assert!((*a - expected).length() <= tolerance, "body {}", i);
+    }
+}
@@ -0,0 +1,248 @@
+mod common;
+
+use common::{random_disk, scenarios::system};
+use Wallfacer::{
+    collision::{sweep_and_prune, time_of_impact, CollisionResponse, Fragmentation},
+    physics::{GravityModel, Softening},
+    planet::{Planet, PlanetColor},
+    scalar::{Float, Vector},
+};
+
+fn ball(name: &'static str, x: Float, radius: Float, vx: Float, mass: Float) -> Planet<'static> {
+    Planet::new(
+        name,
+        Vector::new(x, 0.0),
+        radius,
+        Vector::new(vx, 0.0),
+        mass,
+        PlanetColor::white(),
+    )
+}
+
+#[test]
+fn pile_up_merges_in_one_update() {
+    // A touches B and B touches C but A and C are apart, D touches nobody
+    let mut system = system(1e-9, vec![]);
+    let a = system.add_planet(ball("A", 0.0, 1.0, 1.0, 1.0));
+    let b = system.add_planet(ball("B", 1.5, 1.0, 0.0, 4.0));
+    let c = system.add_planet(ball("C", 3.0, 1.0, -1.0, 2.0));
+    let d = system.add_planet(ball("D", 100.0, 1.0, 0.0, 1.0));
+    system.update_system();
+
+    assert_eq!(system.bodies.len(), 2);
+    let events = system.last_merges();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].survivor, b);
+    assert_eq!(events[0].absorbed, vec![a, c]);
+    assert_eq!(events[0].mass, 7.0);
+
+    let survivor = system.planet(system.index_of(b).unwrap());
+    assert_eq!(survivor.name, "B");
+    assert_eq!(survivor.mass, 7.0);
+    assert!((survivor.vel.x - (1.0 - 2.0) / 7.0).abs() < 1e-6);
+    assert!((survivor.pos.x - (1.5 * 4.0 + 3.0 * 2.0) / 7.0).abs() < 1e-6);
+    assert_eq!(survivor.radius, 1.0);
+    assert_eq!(system.index_of(a), None);
+    assert_eq!(system.index_of(d), Some(1));
+
+    system.update_system();
+    assert!(system.last_merges().is_empty());
+}
+
+#[test]
+fn merged_radius_can_combine_volumes() {
+    let mut system = system(
+        1e-9,
+        vec![ball("A", 0.0, 1.0, 0.0, 1.0), ball("B", 1.0, 2.0, 0.0, 1.0)],
+    );
+    system.set_combine_volume(true);
+    system.update_system();
+    assert_eq!(system.bodies.len(), 1);
+    let radius = system.planet(0).radius;
+    assert!((radius - (9.0 as Float).cbrt()).abs() < 1e-6);
+}
+
+#[test]
+fn elastic_bounce_conserves_momentum_and_energy() {
+    let mut system = system(
+        1e-9,
+        vec![
+            ball("A", 0.0, 1.0, 2.0, 1.0),
+            ball("B", 1.9, 1.0, -1.0, 3.0),
+        ],
+    );
+    system.set_collision_response(CollisionResponse::Elastic);
+    let before = system.diagnostics();
+    system.update_system();
+    let after = system.diagnostics();
+
+    assert_eq!(system.bodies.len(), 2);
+    assert!((after.momentum - before.momentum).length() < 1e-6);
+    assert!((after.kinetic - before.kinetic).abs() < 1e-6 * before.kinetic);
+    // separating, and pushed apart until they just touch
+    assert!(system.planet(1).vel.x > system.planet(0).vel.x);
+    let gap = system.planet(1).pos.x - system.planet(0).pos.x;
+    assert!((gap - 2.0).abs() < 1e-6);
+}
+
+#[test]
+fn restitution_scales_the_separation_speed() {
+    let mut system = system(
+        1e-9,
+        vec![
+            ball("A", 0.0, 1.0, 1.0, 1.0),
+            ball("B", 1.9, 1.0, -1.0, 1.0),
+        ],
+    );
+    system.set_collision_response(CollisionResponse::Inelastic { restitution: 0.5 });
+    system.update_system();
+    let (a, b) = (system.planet(0), system.planet(1));
+    assert!((b.vel.x - a.vel.x - 0.5 * 2.0).abs() < 1e-6);
+    assert!((a.vel.x + b.vel.x).abs() < 1e-6);
+}
+
+#[test]
+fn pairs_can_override_the_global_response() {
+    // everything merges except A and B, which bounce
+    let mut system = system(1e-9, vec![]);
+    let a = system.add_planet(ball("A", 0.0, 1.0, 1.0, 1.0));
+    let b = system.add_planet(ball("B", 1.9, 1.0, -1.0, 1.0));
+    let c = system.add_planet(ball("C", 50.0, 1.0, 0.0, 1.0));
+    let d = system.add_planet(ball("D", 51.0, 1.0, 0.0, 2.0));
+    system.set_pair_response(b, a, CollisionResponse::Elastic);
+    system.update_system();
+
+    assert_eq!(system.bodies.len(), 3);
+    assert_eq!(system.last_merges().len(), 1);
+    assert_eq!(system.last_merges()[0].survivor, d);
+    assert_eq!(system.last_merges()[0].absorbed, vec![c]);
+    assert!((system.planet(0).vel.x + 1.0).abs() < 1e-6);
+    assert!((system.planet(1).vel.x - 1.0).abs() < 1e-6);
+}
+
+fn shattering() -> Fragmentation {
+    Fragmentation {
+        threshold: 0.1,
+        fragments: 6,
+        energy_retained: 0.5,
+    }
+}
+
+#[test]
+fn fast_impacts_shatter_conserving_mass_and_momentum() {
+    let mut system = system(1e-9, vec![]);
+    let a = system.add_planet(ball("A", 0.0, 1.0, 3.0, 2.0));
+    let b = system.add_planet(ball("B", 1.9, 1.0, -1.0, 1.0));
+    system.set_fragmentation(Some(shattering()));
+    let before = system.diagnostics();
+    system.update_system();
+    let after = system.diagnostics();
+
+    assert!(system.last_merges().is_empty());
+    let events = system.last_fragmentations();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].parents, vec![a, b]);
+    assert_eq!(events[0].fragments.len(), 6);
+    // (1/2) (2/3) 4^2 / 3
+    assert!((events[0].specific_energy - 16.0 / 9.0).abs() < 1e-6);
+    assert_eq!(system.bodies.len(), 6);
+    assert!((after.mass - before.mass).abs() < 1e-6);
+    assert!((after.momentum - before.momentum).length() < 1e-6);
+    assert!((after.center_of_mass - before.center_of_mass).length() < 1e-6);
+    assert!((after.kinetic - before.kinetic).abs() > 1e-3);
+
+    // the fragments fly apart rather than colliding again
+    system.update_system();
+    assert!(system.last_merges().is_empty());
+    assert!(system.last_fragmentations().is_empty());
+    assert_eq!(system.bodies.len(), 6);
+}
+
+#[test]
+fn slow_impacts_still_merge() {
+    let mut system = system(
+        1e-9,
+        vec![
+            ball("A", 0.0, 1.0, 0.1, 2.0),
+            ball("B", 1.9, 1.0, -0.1, 1.0),
+        ],
+    );
+    system.set_fragmentation(Some(shattering()));
+    system.update_system();
+    assert!(system.last_fragmentations().is_empty());
+    assert_eq!(system.last_merges().len(), 1);
+    assert_eq!(system.bodies.len(), 1);
+}
+
+#[test]
+fn sweep_and_prune_finds_every_overlap() {
+    let (pos, mass) = random_disk(2000);
+    let radius: Vec<Float> = mass.iter().map(|m| 1e-3 * m.cbrt()).collect();
+    let mut brute = vec![];
+    for i in 0..pos.len() {
+        for j in i + 1..pos.len() {
+            let reach = radius[i] + radius[j];
+            if (pos[i].x - pos[j].x).abs() <= reach && (pos[i].y - pos[j].y).abs() <= reach {
+                brute.push((i, j));
+            }
+        }
+    }
+    assert!(brute.len() > 100);
+    assert_eq!(sweep_and_prune(&pos, &radius), brute);
+}
+
+#[test]
+fn time_of_impact_of_approaching_circles() {
+    let offset = Vector::new(10.0, 0.0);
+    assert_eq!(
+        time_of_impact(offset, Vector::new(-20.0, 0.0), 2.0),
+        Some(0.4)
+    );
+    assert_eq!(time_of_impact(offset, Vector::new(20.0, 0.0), 2.0), None);
+    assert_eq!(time_of_impact(offset, Vector::new(-5.0, 0.0), 2.0), None);
+    assert_eq!(time_of_impact(offset, Vector::new(-20.0, 5.0), 1.0), None);
+    assert_eq!(
+        time_of_impact(Vector::new(1.0, 0.0), Vector::ZERO, 2.0),
+        Some(0.0)
+    );
+}
+
+// two small bodies that swap places within a single step of 1, so checking
+// only the end of each step never sees them touch
+fn head_on(timestep: Float, response: CollisionResponse) -> Vec<Planet<'static>> {
+    let mut system = system(
+        timestep,
+        vec![
+            ball("A", -5.0, 0.1, 10.0, 1.0),
+            ball("B", 5.0, 0.1, -10.0, 1.0),
+        ],
+    );
+    system.set_gravity(GravityModel::new(0.0, Softening::None));
+    system.set_collision_response(response);
+    while system.time < 1.0 - 1e-6 {
+        system.update_system();
+    }
+    system.planets().collect()
+}
+
+#[test]
+fn fast_bodies_do_not_tunnel() {
+    let merged = head_on(1.0, CollisionResponse::Merge);
+    assert_eq!(merged.len(), 1);
+    assert!(merged[0].pos.length() < 1e-6);
+
+    // they meet at t = 0.49 and each travels back 5.1 by t = 1
+    let bounced = head_on(1.0, CollisionResponse::Elastic);
+    assert!((bounced[0].pos.x + 5.2).abs() < 1e-6);
+    assert!((bounced[1].pos.x - 5.2).abs() < 1e-6);
+}
+
+#[test]
+fn outcome_does_not_depend_on_the_timestep() {
+    let coarse = head_on(1.0, CollisionResponse::Elastic);
+    let fine = head_on(0.01, CollisionResponse::Elastic);
+    for (a, b) in coarse.iter().zip(&fine) {
+        assert!(a.pos.distance(b.pos) < 1e-4);
+        assert!(a.vel.distance(b.vel) < 1e-6);
+    }
+}
@@ -0,0 +1,38 @@
+// each test binary uses its own subset of these helpers
+#![allow(dead_code)]
+
+pub mod scenarios;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use Wallfacer::{
+    physics::{DirectSum, ForceSolver, GravityModel},
+    scalar::{Float, Vector},
+};
+
+// a clumpy disk of bodies with a wide spread of masses
+pub fn random_disk(n: usize) -> (Vec<Vector>, Vec<Float>) {
+    let mut rng = StdRng::seed_from_u64(7);
+    let pos = (0..n)
+        .map(|_| {
+            let r: Float = 400.0 * rng.gen::<Float>().powi(2);
+            let angle: Float = rng.gen_range(0.0..std::f64::consts::TAU as Float);
+            Vector::new(600.0 + r * angle.cos(), 400.0 + r * angle.sin())
+        })
+        .collect();
+    let mass = (0..n).map(|_| rng.gen_range(1e6..1e9)).collect();
+    (pos, mass)
+}
+
+// root mean square of |a - a_direct| over that of |a_direct|
+pub fn rms_relative_error(solver: &dyn ForceSolver, pos: &[Vector], mass: &[Float]) -> Float {
+    let gravity = GravityModel::default();
+    let exact = DirectSum.accelerations(&gravity, pos, mass);
+    let approx = solver.accelerations(&gravity, pos, mass);
+    let error: Float = exact
+        .iter()
+        .zip(&approx)
+        .map(|(e, a)| (*a - *e).length_squared())
+        .sum();
+    let norm: Float = exact.iter().map(|e| e.length_squared()).sum();
+    (error / norm).sqrt()
+}
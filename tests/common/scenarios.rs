@@ -0,0 +1,91 @@
+// Known configurations with analytic answers, all in G = 1 units.
+use Wallfacer::{
+    physics::{GravityModel, Softening},
+    planet::{Planet, PlanetColor},
+    scalar::{Float, Vector},
+    system::PlanetSystem,
+};
+
+pub const TAU: Float = std::f64::consts::TAU as Float;
+
+pub fn body(name: &'static str, pos: Vector, vel: Vector, mass: Float) -> Planet<'static> {
+    // small enough that none of the scenarios collide
+    Planet::new(name, pos, 1e-3, vel, mass, PlanetColor::white())
+}
+
+pub fn system(timestep: Float, planets: Vec<Planet<'static>>) -> PlanetSystem<'static> {
+    let mut system = PlanetSystem::from_vec(timestep, planets);
+    system.set_gravity(GravityModel::new(1.0, Softening::None));
+    system
+}
+
+// a light body on an orbit of semi-major axis 1 and eccentricity e about a
+// heavy one, starting at pericentre, in the centre of mass frame. Returns the
+// bodies and the orbital period.
+pub fn kepler(eccentricity: Float) -> (Vec<Planet<'static>>, Float) {
+    let (m1, m2) = (1.0, 1e-3);
+    let total = m1 + m2;
+    let pericentre = 1.0 - eccentricity;
+    let speed = (total * (1.0 + eccentricity) / pericentre).sqrt();
+    let planets = vec![
+        body(
+            "Star",
+            Vector::new(-pericentre * m2 / total, 0.0),
+            Vector::new(0.0, -speed * m2 / total),
+            m1,
+        ),
+        body(
+            "Planet",
+            Vector::new(pericentre * m1 / total, 0.0),
+            Vector::new(0.0, speed * m1 / total),
+            m2,
+        ),
+    ];
+    (planets, TAU / total.sqrt())
+}
+
+// the figure-eight choreography of three equal masses (Chenciner & Montgomery
+// 2000, initial conditions from Simo to the seven digits single precision holds)
+pub fn figure_eight() -> (Vec<Planet<'static>>, Float) {
+    let x1 = Vector::new(-0.970_004_4, 0.243_087_5);
+    let v3 = Vector::new(-0.932_407_4, -0.864_731_5);
+    let planets = vec![
+        body("A", x1, -v3 / 2.0, 1.0),
+        body("B", -x1, -v3 / 2.0, 1.0),
+        body("C", Vector::ZERO, v3, 1.0),
+    ];
+    (planets, 6.325_914)
+}
+
+// time of the first full turn of body about centre, interpolated between updates
+pub fn measure_period(
+    system: &mut PlanetSystem,
+    body: usize,
+    centre: usize,
+    limit: Float,
+) -> Float {
+    let angle = |system: &PlanetSystem| {
+        let offset = system.planet(body).pos - system.planet(centre).pos;
+        offset.y.atan2(offset.x)
+    };
+    let start = angle(system);
+    let mut turned = 0.0;
+    let mut last = start;
+    while system.time < limit {
+        system.update_system();
+        let now = angle(system);
+        let mut step = now - last;
+        if step < -TAU / 2.0 {
+            step += TAU;
+        } else if step > TAU / 2.0 {
+            step -= TAU;
+        }
+        if turned + step >= TAU {
+            let fraction = (TAU - turned) / step;
+            return system.time - system.timestep * (1.0 - fraction);
+        }
+        turned += step;
+        last = now;
+    }
+    panic!("no full turn within {}", limit);
+}
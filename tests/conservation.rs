@@ -0,0 +1,109 @@
+// long integrations against analytic answers need double precision
+#![cfg(not(feature = "single-precision"))]
+
+mod common;
+
+use common::scenarios::{body, figure_eight, kepler, measure_period, system};
+use Wallfacer::{
+    ias15::Ias15,
+    integrator::{Yoshida4, Yoshida6},
+    scalar::Vector,
+};
+
+#[test]
+fn circular_orbit_has_the_kepler_period() {
+    let (planets, period) = kepler(0.0);
+    let mut system = system(period / 2000.0, planets);
+    system.set_integrator(Box::new(Yoshida4));
+    let measured = measure_period(&mut system, 1, 0, 2.0 * period);
+    assert!(
+        (measured - period).abs() < 1e-6 * period,
+        "{} vs {}",
+        measured,
+        period
+    );
+
+    let drift = system.drift().unwrap();
+    assert!(drift.energy < 1e-10, "energy drift {:e}", drift.energy);
+    assert!(
+        drift.momentum < 1e-12,
+        "momentum drift {:e}",
+        drift.momentum
+    );
+    assert!(
+        drift.angular_momentum < 1e-12,
+        "angular momentum drift {:e}",
+        drift.angular_momentum
+    );
+}
+
+#[test]
+fn kepler_ellipse_closes_after_one_period() {
+    let (planets, period) = kepler(0.6);
+    let start = planets[1].pos - planets[0].pos;
+    let steps = 400;
+    let mut system = system(period / steps as f64, planets);
+    system.set_integrator(Box::new(Ias15::new()));
+    for _ in 0..steps {
+        system.update_system();
+    }
+    let end = system.planet(1).pos - system.planet(0).pos;
+    assert!(end.distance(start) < 1e-8, "{} vs {}", end, start);
+
+    let drift = system.drift().unwrap();
+    assert!(drift.energy < 1e-11, "energy drift {:e}", drift.energy);
+    assert!(
+        drift.momentum < 1e-12,
+        "momentum drift {:e}",
+        drift.momentum
+    );
+    assert!(
+        drift.angular_momentum < 1e-11,
+        "angular momentum drift {:e}",
+        drift.angular_momentum
+    );
+}
+
+#[test]
+fn figure_eight_repeats_itself() {
+    let (planets, period) = figure_eight();
+    let start: Vec<Vector> = planets.iter().map(|p| p.pos).collect();
+    let steps = 2000;
+    let mut system = system(period / steps as f64, planets);
+    system.set_integrator(Box::new(Yoshida6));
+    for _ in 0..steps {
+        system.update_system();
+    }
+    for (i, start) in start.iter().enumerate() {
+        let end = system.planet(i).pos;
+        assert!(
+            end.distance(*start) < 1e-5,
+            "body {}: {} vs {}",
+            i,
+            end,
+            start
+        );
+    }
+    let now = system.diagnostics();
+    // the choreography has no net momentum and sits at the origin
+    assert!(now.momentum.length() < 1e-12);
+    assert!(now.center_of_mass.length() < 1e-12);
+    let drift = system.drift().unwrap();
+    assert!(drift.energy < 1e-10, "energy drift {:e}", drift.energy);
+}
+
+#[test]
+fn merging_conserves_mass_and_momentum() {
+    // two overlapping bodies merge on the first update
+    let planets = vec![
+        body("Big", Vector::new(0.0, 0.0), Vector::new(0.1, 0.0), 3.0),
+        body("Small", Vector::new(5e-4, 0.0), Vector::new(-0.2, 0.1), 1.0),
+    ];
+    let mut system = system(1e-6, planets);
+    let before = system.diagnostics();
+    system.update_system();
+    assert_eq!(system.bodies.len(), 1);
+    let after = system.diagnostics();
+    assert_eq!(after.mass, before.mass);
+    assert!((after.momentum - before.momentum).length() < 1e-12);
+}
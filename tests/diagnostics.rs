@@ -0,0 +1,65 @@
+use Wallfacer::{
+    diagnostics::Diagnostics,
+    physics::{GravityModel, Softening},
+    planet::{Planet, PlanetColor},
+    scalar::{Float, Vector},
+};
+
+fn close(a: Float, b: Float) -> bool {
+    (a - b).abs() <= 1e-5 * b.abs().max(1.0)
+}
+
+#[test]
+fn two_bodies_by_hand() {
+    let gravity = GravityModel::new(2.0, Softening::None);
+    let pos = [Vector::new(0.0, 0.0), Vector::new(3.0, 4.0)];
+    let vel = [Vector::new(1.0, 0.0), Vector::new(0.0, -2.0)];
+    let mass = [1.0, 3.0];
+    let d = Diagnostics::measure(&gravity, &pos, &vel, &mass);
+
+    assert!(close(d.kinetic, 0.5 * 1.0 * 1.0 + 0.5 * 3.0 * 4.0));
+    // -G m1 m2 / r with r = 5
+    assert!(close(d.potential, -2.0 * 3.0 / 5.0));
+    assert!(close(d.energy(), 6.5 - 1.2));
+    assert_eq!(d.momentum, Vector::new(1.0, -6.0));
+    // 3 * (3 * -2 - 4 * 0)
+    assert!(close(d.angular_momentum, -18.0));
+    assert_eq!(d.center_of_mass, Vector::new(2.25, 3.0));
+    assert_eq!(d.mass, 4.0);
+}
+
+#[test]
+fn drift_is_relative_to_the_initial_values() {
+    let gravity = GravityModel::new(1.0, Softening::None);
+    let pos = [Vector::new(-1.0, 0.0), Vector::new(1.0, 0.0)];
+    let vel = [Vector::new(0.0, -0.5), Vector::new(0.0, 0.5)];
+    let mass = [1.0, 1.0];
+    let initial = Diagnostics::measure(&gravity, &pos, &vel, &mass);
+    let same = initial.drift(&initial);
+    assert_eq!(
+        (same.energy, same.momentum, same.angular_momentum),
+        (0.0, 0.0, 0.0)
+    );
+
+    // one body speeds up: total momentum was zero, so its drift is measured
+    // against the sum of the bodies' own momenta
+    let faster = [Vector::new(0.0, -0.5), Vector::new(0.0, 0.6)];
+    let drift = Diagnostics::measure(&gravity, &pos, &faster, &mass).drift(&initial);
+    assert!(close(drift.momentum, 0.1 / 1.0));
+    // E goes from 0.25 - 0.5 to 0.305 - 0.5
+    assert!(close(drift.energy, 0.055 / 0.25));
+    assert!(close(drift.angular_momentum, 0.1 / 1.0));
+}
+
+#[test]
+fn kinetic_energy_of_a_planet() {
+    let planet = Planet::new(
+        "Moon",
+        Vector::ZERO,
+        1.0,
+        Vector::new(3.0, 4.0),
+        2.0,
+        PlanetColor::white(),
+    );
+    assert_eq!(planet.calc_energy(), 25.0);
+}
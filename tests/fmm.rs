@@ -0,0 +1,17 @@
+// the tolerances below are for double precision, f32 rounding alone exceeds them
+#![cfg(not(feature = "single-precision"))]
+
+mod common;
+
+use common::{random_disk, rms_relative_error};
+use Wallfacer::fmm::Fmm;
+
+#[test]
+fn fmm_matches_direct_sum() {
+    let (pos, mass) = random_disk(2000);
+    let low = rms_relative_error(&Fmm::new(2), &pos, &mass);
+    let high = rms_relative_error(&Fmm::new(8), &pos, &mass);
+    assert!(low < 1e-4, "order 2 relative error {}", low);
+    // raising the order buys accuracy
+    assert!(high < low / 10.0, "order 8 relative error {}", high);
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use common::scenarios::{body, system, TAU};
+use Wallfacer::{
+    bodies::BodyId,
+    forces::{BodyState, CustomForce, Drag, Force, RadiationPressure, UniformField},
+    integrator::{RungeKutta4, VelocityVerlet},
+    scalar::{Float, Vector},
+};
+
+#[test]
+fn uniform_field_throws_a_parabola() {
+    let mut system = system(
+        0.01,
+        vec![body("Ball", Vector::ZERO, Vector::new(1.0, 2.0), 1.0)],
+    );
+    system.set_integrator(Box::new(VelocityVerlet));
+    system.add_force(Box::new(UniformField {
+        accel: Vector::new(0.0, -1.0),
+    }));
+    for _ in 0..100 {
+        system.update_system();
+    }
+    // x = v t + g t^2 / 2 at t = 1
+    assert!(system.planet(0).pos.distance(Vector::new(1.0, 1.5)) < 1e-4);
+    assert_eq!(system.forces()[0].name(), "Uniform field");
+}
+
+#[test]
+fn drag_reaches_terminal_velocity() {
+    // cross-section 1, so the terminal speed is sqrt(2 m g / (rho Cd A)) = sqrt(2)
+    let mut ball = body("Ball", Vector::ZERO, Vector::ZERO, 1.0);
+    ball.radius = 1.0 / (std::f64::consts::PI as Float).sqrt();
+    let mut system = system(0.01, vec![ball]);
+    system.set_integrator(Box::new(RungeKutta4));
+    system.add_force(Box::new(UniformField {
+        accel: Vector::new(0.0, -1.0),
+    }));
+    system.add_force(Box::new(Drag {
+        density: 1.0,
+        coefficient: 1.0,
+        wind: Vector::ZERO,
+    }));
+    for _ in 0..2000 {
+        system.update_system();
+    }
+    let vel = system.planet(0).vel;
+    assert!((vel.y + (2.0 as Float).sqrt()).abs() < 1e-3);
+    assert!(vel.x.abs() < 1e-6);
+}
+
+#[test]
+fn radiation_pushes_away_from_the_star() {
+    let radiation = RadiationPressure {
+        star: BodyId(0),
+        luminosity: 16.0,
+        speed_of_light: 1.0,
+        efficiency: 1.0,
+    };
+    let state = BodyState {
+        id: &[BodyId(0), BodyId(1)],
+        pos: &[Vector::ZERO, Vector::new(2.0, 0.0)],
+        vel: &[Vector::ZERO; 2],
+        mass: &[1.0, 0.25],
+        radius: &[1.0, 0.5],
+    };
+    let mut accel = vec![Vector::ZERO; 2];
+    radiation.add_accelerations(&state, &mut accel);
+    // L A / (4 pi d^2 c m) = 16 (pi / 4) / (16 pi / 4)
+    assert_eq!(accel[0], Vector::ZERO);
+    assert!(accel[1].distance(Vector::new(1.0, 0.0)) < 1e-6);
+}
+
+#[test]
+fn closures_add_new_physics() {
+    // a spring pulling every body back to the origin, one period is 2 pi
+    let mut system = system(
+        TAU / 1000.0,
+        vec![body("Bob", Vector::new(1.0, 0.0), Vector::ZERO, 1.0)],
+    );
+    system.set_integrator(Box::new(RungeKutta4));
+    system.add_force(Box::new(CustomForce::new("Spring", |bodies, i| {
+        -bodies.pos[i]
+    })));
+    for _ in 0..1000 {
+        system.update_system();
+    }
+    assert!(system.planet(0).pos.distance(Vector::new(1.0, 0.0)) < 1e-4);
+    assert_eq!(system.forces()[0].name(), "Spring");
+    system.clear_forces();
+    assert!(system.forces().is_empty());
+}
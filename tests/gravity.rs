@@ -0,0 +1,79 @@
+// most of these checks only run in double precision
+#![cfg_attr(feature = "single-precision", allow(unused_imports, dead_code))]
+
+use Wallfacer::{
+    physics::{calc_accelerations, calc_point_accel, calc_point_jerk, GravityModel, Softening},
+    scalar::{Float, Vector},
+};
+
+fn models() -> [GravityModel; 3] {
+    [
+        GravityModel::new(1.0, Softening::None),
+        GravityModel::new(1.0, Softening::Plummer(0.5)),
+        GravityModel::new(1.0, Softening::Spline(1.0)),
+    ]
+}
+
+#[test]
+fn softening_keeps_coincident_bodies_finite() {
+    let pos = vec![
+        Vector::new(1.0, 2.0),
+        Vector::new(1.0, 2.0),
+        Vector::new(1.0, 2.1),
+    ];
+    let mass = vec![1.0, 2.0, 3.0];
+    for softening in [Softening::Plummer(0.1), Softening::Spline(0.3)] {
+        let gravity = GravityModel::new(1.0, softening);
+        for a in calc_accelerations(&gravity, &pos, &mass) {
+            assert!(a.is_finite(), "{:?}", softening);
+        }
+    }
+}
+
+// finite differences need double precision
+#[cfg(not(feature = "single-precision"))]
+#[test]
+fn force_is_minus_the_gradient_of_the_potential() {
+    // the radii cover both pieces of the spline and the Newtonian tail
+    let step = 1e-6;
+    for gravity in models() {
+        for r in [0.2, 0.4, 0.7, 0.95, 1.5, 3.0] {
+            let force = calc_point_accel(&gravity, Vector::new(r, 0.0), Vector::ZERO, 2.0).x;
+            let slope = (gravity.potential((r + step) * (r + step), 2.0)
+                - gravity.potential((r - step) * (r - step), 2.0))
+                / (2.0 * step);
+            assert!((force + slope).abs() < 1e-6, "{:?} at r = {}", gravity, r);
+        }
+    }
+    // beyond its radius the spline is plain Newtonian gravity
+    let spline = GravityModel::new(1.0, Softening::Spline(1.0));
+    let point = Vector::new(1.2, 0.5);
+    let newtonian = calc_point_accel(&models()[0], point, Vector::ZERO, 2.0);
+    let softened = calc_point_accel(&spline, point, Vector::ZERO, 2.0);
+    assert!((softened - newtonian).length() < 1e-9 * newtonian.length());
+}
+
+// finite differences need double precision
+#[cfg(not(feature = "single-precision"))]
+#[test]
+fn jerk_is_the_time_derivative_of_acceleration() {
+    let dt: Float = 1e-6;
+    let (pos, vel) = (Vector::new(0.3, 0.2), Vector::new(-0.4, 1.1));
+    let source_vel = Vector::new(0.2, -0.3);
+    // sources inside both pieces of the spline
+    for source in [Vector::new(-0.1, 0.05), Vector::new(-0.4, -0.4)] {
+        for gravity in models() {
+            let accel_at =
+                |t: Float| calc_point_accel(&gravity, pos + vel * t, source + source_vel * t, 1.5);
+            let expected = (accel_at(dt) - accel_at(-dt)) / (2.0 * dt);
+            let jerk = calc_point_jerk(&gravity, pos, vel, source, source_vel, 1.5);
+            assert!(
+                (jerk - expected).length() < 1e-5 * expected.length(),
+                "{:?}: {} vs {}",
+                gravity,
+                jerk,
+                expected
+            );
+        }
+    }
+}
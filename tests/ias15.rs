@@ -0,0 +1,78 @@
+// single precision cannot resolve the close encounters of this problem
+#![cfg(not(feature = "single-precision"))]
+
+use Wallfacer::{
+    ias15::Ias15,
+    integrator::*,
+    physics::{calc_accelerations, GravityModel, Softening},
+    scalar::{Float, Vector},
+};
+
+// the problem is posed in G = 1 units
+const G: Float = 1.0;
+
+// Burrau's Pythagorean problem: masses 3, 4 and 5 at rest on the corners of a
+// 3-4-5 triangle
+fn pythagorean() -> (Vec<Vector>, Vec<Vector>, Vec<Float>) {
+    let pos = vec![
+        Vector::new(1.0, 3.0),
+        Vector::new(-2.0, -1.0),
+        Vector::new(1.0, -1.0),
+    ];
+    let vel = vec![Vector::ZERO; 3];
+    let mass = vec![3.0, 4.0, 5.0];
+    (pos, vel, mass)
+}
+
+fn total_energy(pos: &[Vector], vel: &[Vector], mass: &[Float]) -> Float {
+    let mut energy = 0.0;
+    for i in 0..pos.len() {
+        energy += 0.5 * mass[i] * vel[i].length_squared();
+        for j in i + 1..pos.len() {
+            energy -= G * mass[i] * mass[j] / pos[i].distance(pos[j]);
+        }
+    }
+    energy
+}
+
+fn relative_energy_error(integrator: &mut dyn Integrator, steps: usize, timestep: Float) -> Float {
+    let (mut pos, mut vel, mass) = pythagorean();
+    let gravity = GravityModel::new(G, Softening::None);
+    let accel = |pos: &[Vector], _vel: &[Vector]| calc_accelerations(&gravity, pos, &mass);
+    let initial = total_energy(&pos, &vel, &mass);
+    for _ in 0..steps {
+        integrator.step(&mut pos, &mut vel, timestep, &accel);
+    }
+    ((total_energy(&pos, &vel, &mass) - initial) / initial).abs()
+}
+
+#[test]
+fn ias15_conserves_energy_on_pythagorean_problem() {
+    // through all the close encounters up to the ejection of the binary near t = 60
+    let error = relative_energy_error(&mut Ias15::new(), 70, 1.0);
+    assert!(error < 3e-10, "relative energy error {}", error);
+}
+
+#[test]
+fn tighter_epsilon_still_finishes_and_conserves_energy() {
+    // rounding in the closest encounters used to stall the step control here
+    let error = relative_energy_error(&mut Ias15::with_epsilon(1e-12), 70, 1.0);
+    assert!(error < 1e-10, "relative energy error {}", error);
+}
+
+#[test]
+fn eccentric_orbit_keeps_energy_to_machine_precision() {
+    // e = 0.9 about a fixed unit mass, a hundred orbits of ten steps each
+    let mut pos = vec![Vector::new(0.1, 0.0)];
+    let mut vel = vec![Vector::new(0.0, (19.0 as Float).sqrt())];
+    let central =
+        |pos: &[Vector], _vel: &[Vector]| pos.iter().map(|&p| -p / p.length().powi(3)).collect();
+    let energy = |p: Vector, v: Vector| 0.5 * v.length_squared() - 1.0 / p.length();
+    let initial = energy(pos[0], vel[0]);
+    let mut ias15 = Ias15::new();
+    for _ in 0..1000 {
+        ias15.step(&mut pos, &mut vel, std::f64::consts::TAU / 10.0, &central);
+    }
+    let error = ((energy(pos[0], vel[0]) - initial) / initial).abs();
+    assert!(error < 1e-13, "relative energy error {}", error);
+}
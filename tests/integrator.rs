@@ -0,0 +1,115 @@
+// the convergence orders need double precision to show above rounding
+#![cfg(not(feature = "single-precision"))]
+
+use Wallfacer::{
+    integrator::*,
+    orbit::propagate,
+    scalar::{Float, Vector},
+};
+
+// a body on an ellipse of eccentricity 0.5 about a fixed unit mass at the
+// origin, G = 1, starting at pericentre
+fn ellipse() -> (Vector, Vector) {
+    (Vector::new(0.5, 0.0), Vector::new(0.0, 3.0_f64.sqrt()))
+}
+
+fn central(pos: &[Vector], _vel: &[Vector]) -> Vec<Vector> {
+    pos.iter().map(|&p| -p / p.length().powi(3)).collect()
+}
+
+// distance from the analytic position after one orbit taken in steps steps
+fn error(integrator: &mut dyn Integrator, steps: usize) -> Float {
+    let (start, start_vel) = ellipse();
+    let period = std::f64::consts::TAU;
+    let (mut pos, mut vel) = (vec![start], vec![start_vel]);
+    for _ in 0..steps {
+        integrator.step(&mut pos, &mut vel, period / steps as Float, &central);
+    }
+    let (expected, _) = propagate(start, start_vel, 1.0, period);
+    pos[0].distance(expected)
+}
+
+// the order p with error ~ dt^p, from halving the step
+fn order(integrator: &mut dyn Integrator, steps: usize) -> Float {
+    (error(integrator, steps) / error(integrator, 2 * steps)).log2()
+}
+
+#[test]
+fn verlet_and_leapfrog_are_second_order() {
+    let verlet = order(&mut VelocityVerlet, 400);
+    assert!((verlet - 2.0).abs() < 0.2, "{}", verlet);
+    let leapfrog = order(&mut Leapfrog, 400);
+    assert!((leapfrog - 2.0).abs() < 0.2, "{}", leapfrog);
+}
+
+#[test]
+fn runge_kutta_is_fourth_order() {
+    let rk4 = order(&mut RungeKutta4, 400);
+    assert!((rk4 - 4.0).abs() < 0.2, "{}", rk4);
+}
+
+#[test]
+fn runge_kutta_returns_the_acceleration_at_the_end_of_the_step() {
+    let (start, start_vel) = ellipse();
+    let (mut pos, mut vel) = (vec![start], vec![start_vel]);
+    let returned = RungeKutta4.step(&mut pos, &mut vel, 0.1, &central);
+    assert_eq!(returned, central(&pos, &vel));
+}
+
+#[test]
+fn composed_schemes_have_their_orders() {
+    let yoshida4 = order(&mut Yoshida4, 200);
+    assert!((yoshida4 - 4.0).abs() < 0.2, "{}", yoshida4);
+    let forest_ruth = order(&mut ForestRuth, 200);
+    assert!((forest_ruth - 4.0).abs() < 0.2, "{}", forest_ruth);
+    let yoshida6 = order(&mut Yoshida6, 100);
+    assert!((yoshida6 - 6.0).abs() < 0.3, "{}", yoshida6);
+}
+
+fn energy(pos: Vector, vel: Vector) -> Float {
+    0.5 * vel.length_squared() - 1.0 / pos.length()
+}
+
+// worst relative energy error over the first orbits and over the whole run
+fn energy_errors(integrator: &mut dyn Integrator, orbits: usize) -> (Float, Float) {
+    let steps = 100;
+    let (start, start_vel) = ellipse();
+    let initial = energy(start, start_vel);
+    let dt = std::f64::consts::TAU / steps as Float;
+    let (mut pos, mut vel) = (vec![start], vec![start_vel]);
+    let (mut early, mut worst): (Float, Float) = (0.0, 0.0);
+    for orbit in 0..orbits {
+        for _ in 0..steps {
+            integrator.step(&mut pos, &mut vel, dt, &central);
+            worst = worst.max(((energy(pos[0], vel[0]) - initial) / initial).abs());
+        }
+        if orbit < 10 {
+            early = worst;
+        }
+    }
+    (early, worst)
+}
+
+#[test]
+fn symplectic_schemes_keep_energy_bounded() {
+    let schemes: [Box<dyn Integrator>; 4] = [
+        Box::new(Leapfrog),
+        Box::new(Yoshida4),
+        Box::new(Yoshida6),
+        Box::new(ForestRuth),
+    ];
+    for mut scheme in schemes {
+        // the error oscillates over each orbit but does not grow
+        let (early, worst) = energy_errors(scheme.as_mut(), 2000);
+        assert!(
+            worst < 1.5 * early,
+            "{}: {:e} then {:e}",
+            scheme.name(),
+            early,
+            worst
+        );
+    }
+    // where a non-symplectic scheme of higher order drifts away
+    let (early, worst) = energy_errors(&mut RungeKutta4, 2000);
+    assert!(worst > 10.0 * early, "{:e} then {:e}", early, worst);
+}
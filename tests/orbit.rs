@@ -0,0 +1,189 @@
+mod common;
+
+use common::scenarios::TAU;
+use Wallfacer::{
+    orbit::{propagate, solve_kepler, OrbitalElements},
+    physics::GravityModel,
+    planet::{Planet, PlanetColor},
+    scalar::{Float, Vector},
+};
+
+// loose enough for single precision
+const TOLERANCE: Float = 1e-4;
+
+fn close(a: Float, b: Float) -> bool {
+    (a - b).abs() < TOLERANCE * (1.0 + b.abs())
+}
+
+#[test]
+fn kepler_equation_is_solved() {
+    for e in [0.0, 0.1, 0.5, 0.9, 0.99] {
+        for m in [-7.0, -1.0, 0.0, 0.3, 3.0, 10.0] {
+            let anomaly = solve_kepler(m, e);
+            assert!(close(anomaly - e * anomaly.sin(), m), "e {e} M {m}");
+        }
+    }
+    for e in [1.1, 2.0, 5.0] {
+        for m in [-20.0, -1.0, 0.0, 0.5, 20.0] {
+            let anomaly = solve_kepler(m, e);
+            assert!(close(e * anomaly.sinh() - anomaly, m), "e {e} M {m}");
+        }
+    }
+}
+
+#[test]
+fn elements_survive_a_round_trip() {
+    let orbits = [
+        OrbitalElements::new(1.0, 0.3, 0.5, 1.0),
+        OrbitalElements::new(2.5, 0.9, -2.0, -0.2),
+        OrbitalElements::new(-1.0, 1.5, 1.0, 0.7),
+        OrbitalElements {
+            retrograde: true,
+            ..OrbitalElements::new(1.5, 0.6, 2.0, 2.5)
+        },
+    ];
+    for elements in orbits {
+        let (pos, vel) = elements.to_state(2.0);
+        let back = OrbitalElements::from_state(pos, vel, 2.0);
+        assert_eq!(back.retrograde, elements.retrograde);
+        assert!(close(back.semi_major_axis, elements.semi_major_axis));
+        assert!(close(back.eccentricity, elements.eccentricity));
+        assert!(close(
+            back.argument_of_periapsis,
+            elements.argument_of_periapsis
+        ));
+        assert!(close(back.mean_anomaly, elements.mean_anomaly));
+    }
+}
+
+#[test]
+fn states_follow_vis_viva() {
+    // v^2 = mu (2 / r - 1 / a), pericentre at a (1 - e) along the periapsis
+    let elements = OrbitalElements::new(2.0, 0.5, TAU / 4.0, 0.0);
+    let (pos, vel) = elements.to_state(1.0);
+    assert!(pos.distance(Vector::new(0.0, 1.0)) < TOLERANCE);
+    assert!(close(vel.length_squared(), 2.0 / 1.0 - 1.0 / 2.0));
+    assert!(vel.x < 0.0);
+    assert_eq!(elements.periapsis(), 1.0);
+    assert_eq!(elements.apoapsis(), Some(3.0));
+}
+
+#[test]
+#[cfg(not(feature = "single-precision"))]
+fn body_placed_on_an_orbit_keeps_it() {
+    use common::scenarios::{body, system};
+    use Wallfacer::{ias15::Ias15, physics::Softening};
+
+    let gravity = GravityModel::new(1.0, Softening::None);
+    let star = body("Star", Vector::new(3.0, -1.0), Vector::new(0.01, 0.0), 1.0);
+    let elements = OrbitalElements::new(1.0, 0.4, 1.0, 2.0);
+    let planet = Planet::on_orbit(
+        &gravity,
+        &star,
+        "Planet",
+        1e-3,
+        1e-3,
+        PlanetColor::white(),
+        &elements,
+    );
+    let measured = planet.orbital_elements(&gravity, &star);
+    assert!((measured.eccentricity - 0.4).abs() < 1e-12);
+
+    // one period later both bodies are back where they were relative to each other
+    let period = elements.period(1.001).unwrap();
+    let start = planet.pos - star.pos;
+    let mut system = system(period / 400.0, vec![star, planet]);
+    system.set_integrator(Box::new(Ias15::new()));
+    for _ in 0..400 {
+        system.update_system();
+    }
+    let end = system.planet(1).pos - system.planet(0).pos;
+    assert!(end.distance(start) < 1e-8);
+}
+
+#[test]
+fn satellites_start_on_circular_orbits_about_their_sun() {
+    let gravity = GravityModel::default();
+    let sun = Planet::new(
+        "Sun",
+        Vector::new(400.0, 300.0),
+        10.0,
+        Vector::ZERO,
+        1e10,
+        PlanetColor::white(),
+    );
+    for _ in 0..20 {
+        let satellite =
+            Planet::create_satellite(&gravity, &sun, "Moon", 8.0, 400.0, PlanetColor::green());
+        let distance = satellite.pos.distance(sun.pos);
+        assert!((200.0..=400.0).contains(&distance));
+        assert!(satellite.orbital_elements(&gravity, &sun).eccentricity < TOLERANCE);
+    }
+}
+
+#[test]
+fn propagation_advances_the_mean_anomaly() {
+    let mu = 1.5;
+    let orbits = [
+        OrbitalElements::new(1.0, 0.0, 0.0, 0.3),
+        OrbitalElements::new(2.0, 0.7, 1.0, -1.0),
+        OrbitalElements::new(-1.0, 1.8, -0.5, 0.2),
+        OrbitalElements {
+            retrograde: true,
+            ..OrbitalElements::new(1.0, 0.3, 2.0, 0.1)
+        },
+    ];
+    for elements in orbits {
+        let (pos, vel) = elements.to_state(mu);
+        for dt in [0.1, 2.5, -1.3, 40.0] {
+            let later = OrbitalElements {
+                mean_anomaly: elements.mean_anomaly + elements.mean_motion(mu) * dt,
+                ..elements
+            };
+            let (expected_pos, expected_vel) = later.to_state(mu);
+            let (p, v) = propagate(pos, vel, mu, dt);
+            assert!(p.distance(expected_pos) < 1e-3, "{elements:?} dt {dt}");
+            assert!(v.distance(expected_vel) < 1e-3, "{elements:?} dt {dt}");
+        }
+    }
+}
+
+#[test]
+#[cfg(not(feature = "single-precision"))]
+fn propagation_runs_backwards() {
+    let elements = OrbitalElements::new(1.3, 0.6, 0.4, 2.0);
+    let (pos, vel) = elements.to_state(1.0);
+    let (p, v) = propagate(pos, vel, 1.0, 123.4);
+    let (p, v) = propagate(p, v, 1.0, -123.4);
+    assert!(p.distance(pos) < 1e-10);
+    assert!(v.distance(vel) < 1e-10);
+}
+
+#[test]
+#[cfg(not(feature = "single-precision"))]
+fn system_reports_its_error_against_kepler() {
+    use common::scenarios::{kepler, system};
+    use Wallfacer::{bodies::BodyId, ias15::Ias15};
+
+    let run = |accurate: bool| {
+        let (planets, period) = kepler(0.5);
+        let mut system = system(period / 200.0, planets);
+        if accurate {
+            system.set_integrator(Box::new(Ias15::new()));
+        }
+        assert!(system.compare_with_kepler(BodyId(1), BodyId(0)));
+        assert!(!system.compare_with_kepler(BodyId(1), BodyId(7)));
+        for _ in 0..400 {
+            system.update_system();
+        }
+        let errors = system.kepler_errors().to_vec();
+        assert_eq!(errors.len(), 400);
+        assert!((errors[399].0 - 2.0 * period).abs() < 1e-9);
+        errors
+            .iter()
+            .fold(0.0, |worst: Float, &(_, e)| worst.max(e))
+    };
+    assert!(run(true) < 1e-9);
+    // the default first order scheme strays visibly
+    assert!(run(false) > 1e-3);
+}
@@ -0,0 +1,35 @@
+#![cfg(feature = "parallel")]
+
+mod common;
+
+use common::random_disk;
+use Wallfacer::{
+    fmm::Fmm,
+    physics::{calc_jerks, DirectSum, ForceSolver, GravityModel},
+    quadtree::BarnesHut,
+    scalar::Vector,
+};
+
+#[test]
+fn thread_count_does_not_change_results() {
+    // large enough that the tree build is split across threads too
+    let (pos, mass) = random_disk(2000);
+    let vel: Vec<Vector> = pos.iter().map(|p| p.perp() * 1e-3).collect();
+    let gravity = GravityModel::default();
+    let solvers: [&(dyn ForceSolver + Sync); 3] = [&DirectSum, &BarnesHut::new(0.5), &Fmm::new(4)];
+    let serial = rayon::ThreadPoolBuilder::new()
+        .num_threads(1)
+        .build()
+        .unwrap();
+    for solver in solvers {
+        let expected = serial.install(|| solver.accelerations(&gravity, &pos, &mass));
+        assert_eq!(
+            solver.accelerations(&gravity, &pos, &mass),
+            expected,
+            "{}",
+            solver.name()
+        );
+    }
+    let expected = serial.install(|| calc_jerks(&gravity, &pos, &vel, &mass));
+    assert_eq!(calc_jerks(&gravity, &pos, &vel, &mass), expected);
+}
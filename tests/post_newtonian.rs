@@ -0,0 +1,171 @@
+// precession builds up over many orbits, too slowly to see in single precision
+#![cfg(not(feature = "single-precision"))]
+
+mod common;
+
+use common::scenarios::{body, kepler, system, TAU};
+use Wallfacer::{
+    ias15::Ias15,
+    physics::{calc_accel, GravityModel, Softening},
+    planet::{Planet, PlanetColor},
+    scalar::{Float, Vector},
+    system::PlanetSystem,
+    units::UnitSystem,
+};
+
+// angle of the eccentricity vector of the relative orbit, which points at pericentre
+fn pericentre_angle(pos: Vector, vel: Vector, gm: Float) -> Float {
+    let e = ((vel.length_squared() - gm / pos.length()) * pos - pos.dot(vel) * vel) / gm;
+    e.y.atan2(e.x)
+}
+
+#[test]
+fn perihelion_precesses_at_the_analytic_rate() {
+    let eccentricity = 0.2;
+    let c = 100.0;
+    let orbits = 10;
+    let (planets, period) = kepler(eccentricity);
+    let total: Float = planets.iter().map(|p| p.mass).sum();
+    let mut system = system(period / 200.0, planets);
+    system.set_gravity(GravityModel::new(1.0, Softening::None).post_newtonian(c));
+    system.set_integrator(Box::new(Ias15::new()));
+
+    let relative = |system: &PlanetSystem| {
+        let (star, planet) = (system.planet(0), system.planet(1));
+        (planet.pos - star.pos, planet.vel - star.vel)
+    };
+    let (pos, vel) = relative(&system);
+    let start = pericentre_angle(pos, vel, total);
+    for _ in 0..200 * orbits {
+        system.update_system();
+    }
+    let (pos, vel) = relative(&system);
+    let measured = (pericentre_angle(pos, vel, total) - start) / orbits as Float;
+
+    // semi-major axis 1
+    let expected = 3.0 * TAU * total / (c * c * (1.0 - eccentricity * eccentricity));
+    assert!(
+        (measured - expected).abs() < 0.02 * expected,
+        "precession per orbit {measured}, expected {expected}"
+    );
+}
+
+#[test]
+fn comparable_masses_precess_at_the_two_body_rate() {
+    // the rate depends on the total mass alone, and the pull of each body on
+    // the other balances so the 1PN centre of mass, with each mass weighted by
+    // 1 + (v^2 - Gm/r) / 2c^2, moves in a straight line. It does move, the
+    // velocities that zero the Newtonian momentum leave some 1PN momentum.
+    let (m1, m2): (Float, Float) = (1.0, 0.5);
+    let total = m1 + m2;
+    let eccentricity = 0.5;
+    let c = 100.0;
+    let orbits = 10;
+    let pericentre = 1.0 - eccentricity;
+    let speed = (total * (1.0 + eccentricity) / pericentre).sqrt();
+    let planets = vec![
+        body(
+            "A",
+            Vector::new(-pericentre * m2 / total, 0.0),
+            Vector::new(0.0, -speed * m2 / total),
+            m1,
+        ),
+        body(
+            "B",
+            Vector::new(pericentre * m1 / total, 0.0),
+            Vector::new(0.0, speed * m1 / total),
+            m2,
+        ),
+    ];
+    let period = TAU / total.sqrt();
+    let mut system = system(period / 200.0, planets);
+    system.set_gravity(GravityModel::new(1.0, Softening::None).post_newtonian(c));
+    system.set_integrator(Box::new(Ias15::new()));
+
+    let relative = |system: &PlanetSystem| {
+        let (a, b) = (system.planet(0), system.planet(1));
+        (b.pos - a.pos, b.vel - a.vel)
+    };
+    let centre = |system: &PlanetSystem| {
+        let (a, b) = (system.planet(0), system.planet(1));
+        let potential = 1.0 / a.pos.distance(b.pos);
+        let weight = |body: &Planet, other: Float| {
+            body.mass * (1.0 + (body.vel.length_squared() - other * potential) / (2.0 * c * c))
+        };
+        let (wa, wb) = (weight(&a, b.mass), weight(&b, a.mass));
+        (wa * a.pos + wb * b.pos) / (wa + wb)
+    };
+    let (pos, vel) = relative(&system);
+    let start = pericentre_angle(pos, vel, total);
+    let first = centre(&system);
+    for _ in 0..100 * orbits {
+        system.update_system();
+    }
+    let middle = centre(&system);
+    for _ in 0..100 * orbits {
+        system.update_system();
+    }
+    let bend = (centre(&system) - 2.0 * middle + first).length();
+    let (pos, vel) = relative(&system);
+    let measured = (pericentre_angle(pos, vel, total) - start) / orbits as Float;
+
+    let expected = 3.0 * TAU * total / (c * c * (1.0 - eccentricity * eccentricity));
+    assert!(
+        (measured - expected).abs() < 0.02 * expected,
+        "precession per orbit {measured}, expected {expected}"
+    );
+    // up to terms of order 1/c^4; summing test particle terms instead bends it
+    // by 1e-2
+    assert!(bend < 1e-4, "centre of mass strayed {bend} from a line");
+}
+
+#[test]
+fn newtonian_orbits_do_not_precess() {
+    let (planets, period) = kepler(0.2);
+    let total: Float = planets.iter().map(|p| p.mass).sum();
+    let mut system = system(period / 200.0, planets);
+    system.set_integrator(Box::new(Ias15::new()));
+    let angle = |system: &PlanetSystem| {
+        let (star, planet) = (system.planet(0), system.planet(1));
+        pericentre_angle(planet.pos - star.pos, planet.vel - star.vel, total)
+    };
+    let start = angle(&system);
+    for _ in 0..2000 {
+        system.update_system();
+    }
+    assert!((angle(&system) - start).abs() < 1e-8);
+}
+
+#[test]
+fn correction_only_applies_with_a_speed_of_light() {
+    let sun = Planet::new(
+        "Sun",
+        Vector::ZERO,
+        1.0,
+        Vector::ZERO,
+        1.0,
+        PlanetColor::white(),
+    );
+    let planet = Planet::new(
+        "Planet",
+        Vector::new(1.0, 0.0),
+        0.1,
+        Vector::new(0.0, 1.0),
+        0.0,
+        PlanetColor::white(),
+    );
+    let newtonian = GravityModel::new(1.0, Softening::None);
+    assert_eq!(
+        calc_accel(&newtonian, &planet, &sun),
+        Vector::new(-1.0, 0.0)
+    );
+    // massless planet on a circular orbit: (4 - 1) / c^2 outwards on top of the
+    // Newtonian pull
+    let relativistic = newtonian.post_newtonian(10.0);
+    let accel = calc_accel(&relativistic, &planet, &sun);
+    assert!((accel - Vector::new(-1.0 + 0.03, 0.0)).length() < 1e-12);
+
+    let c = UnitSystem::AstronomicalDays.speed_of_light().unwrap();
+    assert!((c - 173.14).abs() < 0.01);
+    assert_eq!(UnitSystem::NBody.speed_of_light(), None);
+}
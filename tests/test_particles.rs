@@ -0,0 +1,88 @@
+mod common;
+
+use common::scenarios::{body, kepler, system, TAU};
+use Wallfacer::{
+    collision::CollisionResponse,
+    integrator::Yoshida4,
+    planet::{Planet, PlanetColor},
+    scalar::{Float, Vector},
+};
+
+#[test]
+fn particles_orbit_without_pulling() {
+    let mut system = system(
+        TAU / 1000.0,
+        vec![body("Star", Vector::ZERO, Vector::ZERO, 1.0)],
+    );
+    system.set_integrator(Box::new(Yoshida4));
+    system.add_test_particle("Dust", Vector::new(1.0, 0.0), Vector::new(0.0, 1.0));
+    for _ in 0..1000 {
+        system.update_system();
+    }
+    assert!(system.planet(1).pos.distance(Vector::new(1.0, 0.0)) < 1e-4);
+    assert_eq!(system.planet(0).pos, Vector::ZERO);
+    assert_eq!(system.planet(0).vel, Vector::ZERO);
+}
+
+#[test]
+fn particles_leave_the_massive_bodies_alone() {
+    let (planets, period) = kepler(0.5);
+    let mut alone = system(period / 500.0, planets.clone());
+    let mut dusty = system(period / 500.0, planets);
+    for k in 0..50 {
+        let angle = TAU * k as Float / 50.0;
+        let pos = 2.0 * Vector::new(angle.cos(), angle.sin());
+        dusty.add_test_particle("Dust", pos, 0.7 * pos.perp());
+    }
+    for _ in 0..500 {
+        alone.update_system();
+        dusty.update_system();
+    }
+    assert_eq!(dusty.bodies.len(), 52);
+    for i in 0..2 {
+        assert_eq!(alone.planet(i).pos, dusty.planet(i).pos);
+        assert_eq!(alone.planet(i).vel, dusty.planet(i).vel);
+    }
+    assert_eq!(alone.diagnostics().energy(), dusty.diagnostics().energy());
+}
+
+fn wall() -> Planet<'static> {
+    Planet::new(
+        "Wall",
+        Vector::ZERO,
+        1.0,
+        Vector::ZERO,
+        1.0,
+        PlanetColor::white(),
+    )
+}
+
+#[test]
+fn particles_pass_through_each_other() {
+    let mut system = system(1.0, vec![]);
+    system.add_test_particle("A", Vector::new(10.0, 0.0), Vector::new(1.0, 0.0));
+    system.add_test_particle("B", Vector::new(11.0, 0.0), Vector::new(-1.0, 0.0));
+    system.update_system();
+    assert_eq!(system.bodies.len(), 2);
+    assert!(system.last_merges().is_empty());
+}
+
+#[test]
+fn particles_are_absorbed_or_bounce_off_massive_bodies() {
+    let mut absorbing = system(1e-6, vec![wall()]);
+    let dust = absorbing.add_test_particle("Dust", Vector::new(0.5, 0.0), Vector::new(-1.0, 0.0));
+    absorbing.update_system();
+    assert_eq!(absorbing.bodies.len(), 1);
+    assert_eq!(absorbing.last_merges()[0].absorbed, vec![dust]);
+    assert_eq!(absorbing.planet(0).mass, 1.0);
+    assert_eq!(absorbing.planet(0).vel, Vector::ZERO);
+
+    let mut bouncing = system(1e-6, vec![wall()]);
+    bouncing.set_collision_response(CollisionResponse::Elastic);
+    bouncing.add_test_particle("Dust", Vector::new(0.5, 0.0), Vector::new(-1.0, 0.0));
+    bouncing.update_system();
+    let (wall, dust) = (bouncing.planet(0), bouncing.planet(1));
+    assert_eq!(wall.vel, Vector::ZERO);
+    assert!((dust.vel.x - 1.0).abs() < 1e-3);
+    assert!(dust.pos.x >= 1.0);
+}
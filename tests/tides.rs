@@ -0,0 +1,97 @@
+mod common;
+
+use common::scenarios::{body, system};
+use Wallfacer::{
+    physics::roche_limit,
+    planet::{Planet, PlanetColor},
+    scalar::{Float, Vector},
+    tides::{TidalDisruption, FLUID, RIGID},
+};
+
+fn moon(x: Float) -> Planet<'static> {
+    Planet::new(
+        "Moon",
+        Vector::new(x, 0.0),
+        0.01,
+        Vector::new(0.0, 1.0),
+        1e-3,
+        PlanetColor::white(),
+    )
+}
+
+fn tides() -> TidalDisruption {
+    TidalDisruption {
+        coefficient: FLUID,
+        mass_ratio: 10.0,
+        min_mass: 2e-4,
+        fragments: 8,
+    }
+}
+
+#[test]
+fn roche_limit_scales_with_the_cube_root_of_the_mass_ratio() {
+    let planet = body("Planet", Vector::ZERO, Vector::ZERO, 1.0);
+    let limit = roche_limit(&moon(1.0), &planet, RIGID);
+    assert!((limit - 1.26 * 0.01 * 10.0).abs() < 1e-6);
+    let limit = roche_limit(&moon(1.0), &planet, FLUID);
+    assert!((limit - 2.44 * 0.01 * 10.0).abs() < 1e-6);
+}
+
+#[test]
+fn moon_inside_the_roche_limit_is_torn_apart() {
+    // the fluid limit is 0.244
+    let mut system = system(1e-6, vec![]);
+    let planet = system.add_planet(body("Planet", Vector::ZERO, Vector::ZERO, 1.0));
+    let moon = system.add_planet(moon(0.2));
+    system.set_tidal_disruption(Some(tides()));
+    let before = system.diagnostics();
+    system.update_system();
+    let after = system.diagnostics();
+
+    let events = system.last_disruptions();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].body, moon);
+    assert_eq!(events[0].primary, planet);
+    assert_eq!(events[0].fragments.len(), 8);
+    assert!(events[0].distance < events[0].roche_limit);
+    assert_eq!(system.bodies.len(), 9);
+    assert!((after.mass - before.mass).abs() < 1e-6);
+    assert!((after.momentum - before.momentum).length() < 1e-6);
+    assert!((after.angular_momentum - before.angular_momentum).abs() < 1e-6);
+
+    // fragments are below min_mass and stay whole
+    system.update_system();
+    assert!(system.last_disruptions().is_empty());
+    assert_eq!(system.bodies.len(), 9);
+}
+
+#[test]
+fn fragments_are_not_torn_apart_again() {
+    // with no strength to hold them, each fragment would be disrupted in turn
+    let mut system = system(1e-6, vec![]);
+    system.add_planet(body("Planet", Vector::ZERO, Vector::ZERO, 1.0));
+    system.add_planet(moon(0.2));
+    system.set_tidal_disruption(Some(TidalDisruption {
+        min_mass: 0.0,
+        ..tides()
+    }));
+    let mut disruptions = 0;
+    for _ in 0..20 {
+        system.update_system();
+        disruptions += system.last_disruptions().len();
+    }
+    assert_eq!(disruptions, 1);
+    assert_eq!(system.bodies.len(), 9);
+}
+
+#[test]
+fn moon_outside_the_roche_limit_stays_whole() {
+    let mut system = system(
+        1e-6,
+        vec![body("Planet", Vector::ZERO, Vector::ZERO, 1.0), moon(0.3)],
+    );
+    system.set_tidal_disruption(Some(tides()));
+    system.update_system();
+    assert!(system.last_disruptions().is_empty());
+    assert_eq!(system.bodies.len(), 2);
+}
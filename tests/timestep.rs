@@ -0,0 +1,63 @@
+mod common;
+
+use common::scenarios::{body, system};
+use Wallfacer::{
+    integrator::{aarseth_timestep, TimestepMode},
+    physics::{calc_accelerations, calc_jerks, GravityModel, Softening},
+    planet::Planet,
+    scalar::Vector,
+};
+
+#[test]
+#[should_panic(expected = "min_step <= max_step")]
+fn adaptive_rejects_a_minimum_above_the_maximum() {
+    TimestepMode::adaptive(0.5, 0.1);
+}
+
+#[test]
+#[should_panic(expected = "0 < min_step")]
+fn adaptive_rejects_a_zero_minimum() {
+    TimestepMode::adaptive(0.0, 0.1);
+}
+
+// the middle body sits where the pulls of the outer two cancel, but one of
+// them moves so the pull on it is changing
+fn balanced_triple() -> Vec<Planet<'static>> {
+    vec![
+        body("Left", Vector::new(-1.0, 0.0), Vector::ZERO, 1.0),
+        body("Middle", Vector::ZERO, Vector::ZERO, 1.0),
+        body("Right", Vector::new(1.0, 0.0), Vector::new(0.0, 1.0), 1.0),
+    ]
+}
+
+#[test]
+fn a_body_with_no_acceleration_does_not_stop_time() {
+    let planets = balanced_triple();
+    let pos: Vec<Vector> = planets.iter().map(|p| p.pos).collect();
+    let vel: Vec<Vector> = planets.iter().map(|p| p.vel).collect();
+    let mass: Vec<_> = planets.iter().map(|p| p.mass).collect();
+    let gravity = GravityModel::new(1.0, Softening::None);
+    let accel = calc_accelerations(&gravity, &pos, &mass);
+    let jerk = calc_jerks(&gravity, &pos, &vel, &mass);
+    assert_eq!(accel[1], Vector::ZERO);
+    assert!(jerk[1].length() > 0.0);
+    assert!(aarseth_timestep(&accel, &jerk, 0.02) > 0.0);
+
+    // the step is set by the outer bodies rather than pinned to the minimum
+    let mut system = system(0.01, planets);
+    system.set_timestep_mode(TimestepMode::adaptive(1e-12, 0.01));
+    system.update_system();
+    assert!((system.time - 0.01).abs() < 1e-6);
+    assert!(system.last_steps().len() < 100);
+}
+
+#[test]
+#[should_panic(expected = "0 < min_step")]
+fn systems_reject_modes_built_by_hand_that_cannot_advance() {
+    let mut system = system(0.01, balanced_triple());
+    system.set_timestep_mode(TimestepMode::Adaptive {
+        eta: 0.02,
+        min_step: 0.0,
+        max_step: 0.0,
+    });
+}
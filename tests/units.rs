@@ -0,0 +1,67 @@
+use Wallfacer::{
+    scalar::{Float, Vector},
+    units::{UnitSystem, Viewport},
+    util::{HEIGHT, WIDTH},
+};
+
+#[test]
+fn astronomical_units_give_the_known_constants() {
+    // the square of Gauss' gravitational constant, AU^3 / (Msun day^2)
+    let gaussian: Float = 0.017_202_1 * 0.017_202_1;
+    let g = UnitSystem::AstronomicalDays.g();
+    assert!((g - gaussian).abs() < 1e-5 * gaussian, "{}", g);
+    // Kepler's third law in years: T^2 = a^3 for the Earth, so G ~ 4 pi^2
+    let four_pi2 = 4.0 * std::f64::consts::PI as Float * std::f64::consts::PI as Float;
+    let g = UnitSystem::AstronomicalYears.g();
+    assert!((g - four_pi2).abs() < 1e-4 * four_pi2, "{}", g);
+    assert_eq!(UnitSystem::NBody.g(), 1.0);
+}
+
+// thousands of steps need double precision
+#[cfg(not(feature = "single-precision"))]
+#[test]
+fn earth_goes_round_once_a_year() {
+    use Wallfacer::{
+        integrator::{Integrator, Leapfrog},
+        physics::calc_accelerations,
+    };
+
+    let gravity = UnitSystem::AstronomicalDays.gravity();
+    let mass = vec![1.0, 3.0e-6];
+    let speed = (gravity.g * (mass[0] + mass[1])).sqrt();
+    let mut pos = vec![Vector::ZERO, Vector::new(1.0, 0.0)];
+    let mut vel = vec![
+        Vector::new(0.0, -speed * mass[1]),
+        Vector::new(0.0, speed * mass[0]),
+    ];
+    let accel = |pos: &[Vector], _vel: &[Vector]| calc_accelerations(&gravity, pos, &mass);
+
+    // a sidereal year, 2 pi / k days, in steps of a tenth of a day
+    let year = 2.0 * std::f64::consts::PI / 0.017_202_098_95 / (1.0 + mass[1]).sqrt();
+    let steps = (year / 0.1).round() as usize;
+    let dt = year / steps as Float;
+    for _ in 0..steps {
+        Leapfrog.step(&mut pos, &mut vel, dt, &accel);
+    }
+    let offset = pos[1] - pos[0];
+    assert!(offset.distance(Vector::new(1.0, 0.0)) < 1e-4, "{}", offset);
+}
+
+#[test]
+fn viewport_maps_world_to_pixels() {
+    let view = Viewport::centered(Vector::new(1.0, -1.0), 100.0);
+    let middle = view.to_screen(Vector::new(1.0, -1.0));
+    assert_eq!(middle.x, WIDTH as f32 / 2.0);
+    assert_eq!(middle.y, HEIGHT as f32 / 2.0);
+    assert_eq!(view.to_screen(Vector::new(2.0, -1.0)).x - middle.x, 100.0);
+    // an Earth sized body in AU is still drawn
+    assert_eq!(view.to_screen_radius(4.26e-5), view.min_radius);
+    assert_eq!(Viewport::pixels().to_screen(Vector::new(3.0, 4.0)).y, 4.0);
+}
+
+#[test]
+fn pixel_systems_have_no_physical_units() {
+    assert_eq!(UnitSystem::Si.energy_unit(), "J");
+    assert_eq!(UnitSystem::Pixels.energy_unit(), "");
+    assert_eq!(UnitSystem::Pixels.time_unit(), "");
+}
@@ -0,0 +1,103 @@
+// long runs against tight bounds need double precision
+#![cfg(not(feature = "single-precision"))]
+
+mod common;
+
+use common::scenarios::{body, system, TAU};
+use Wallfacer::{
+    integrator::{integrator_from_name, Integrator, Leapfrog},
+    orbit::{propagate, OrbitalElements},
+    physics::{GravityModel, Softening},
+    planet::{Planet, PlanetColor},
+    scalar::{Float, Vector},
+    system::PlanetSystem,
+    wisdom_holman::WisdomHolman,
+};
+
+#[test]
+fn lone_orbits_are_exact_with_huge_steps() {
+    // a test particle about a star is pure Kepler drift, four steps an orbit are plenty
+    let mut system = system(
+        TAU / 4.0,
+        vec![body("Star", Vector::ZERO, Vector::ZERO, 1.0)],
+    );
+    system.set_integrator(Box::new(WisdomHolman::new()));
+    let elements = OrbitalElements::new(1.0, 0.6, 0.3, 0.0);
+    let (pos, vel) = elements.to_state(1.0);
+    system.add_test_particle("Dust", pos, vel);
+    for _ in 0..40 {
+        system.update_system();
+    }
+    let (expected, _) = propagate(pos, vel, 1.0, 10.0 * TAU);
+    assert!(system.planet(1).pos.distance(expected) < 1e-9);
+    assert_eq!(system.planet(0).pos, Vector::ZERO);
+}
+
+// a star with two planets of a few Jupiter masses
+fn planetary_system(
+    steps_per_orbit: Float,
+    integrator: Box<dyn Integrator>,
+) -> PlanetSystem<'static> {
+    let gravity = GravityModel::new(1.0, Softening::None);
+    let star = body("Star", Vector::ZERO, Vector::ZERO, 1.0);
+    let inner = Planet::on_orbit(
+        &gravity,
+        &star,
+        "Inner",
+        1e-3,
+        1e-3,
+        PlanetColor::white(),
+        &OrbitalElements::new(1.0, 0.05, 0.0, 0.0),
+    );
+    let outer = Planet::on_orbit(
+        &gravity,
+        &star,
+        "Outer",
+        1e-3,
+        3e-4,
+        PlanetColor::white(),
+        &OrbitalElements::new(1.8, 0.1, 2.0, 1.0),
+    );
+    let mut system = system(TAU / steps_per_orbit, vec![star, inner, outer]);
+    system.set_integrator(integrator);
+    system
+}
+
+fn worst_energy_error(mut system: PlanetSystem, steps: usize) -> Float {
+    let initial = system.diagnostics().energy();
+    let mut worst: Float = 0.0;
+    for _ in 0..steps {
+        system.update_system();
+        worst = worst.max(((system.diagnostics().energy() - initial) / initial).abs());
+    }
+    worst
+}
+
+#[test]
+fn planets_keep_their_energy_with_big_steps() {
+    // 20 steps an inner orbit for 50 orbits
+    let wh = worst_energy_error(planetary_system(20.0, Box::new(WisdomHolman::new())), 1000);
+    let leapfrog = worst_energy_error(planetary_system(20.0, Box::new(Leapfrog)), 1000);
+    assert!(wh < 1e-5, "Wisdom-Holman energy error {wh}");
+    assert!(
+        wh * 100.0 < leapfrog,
+        "Wisdom-Holman {wh}, leapfrog {leapfrog}"
+    );
+}
+
+#[test]
+fn momentum_and_centre_of_mass_are_kept() {
+    let mut system = planetary_system(20.0, Box::new(WisdomHolman::new()));
+    let before = system.diagnostics();
+    for _ in 0..200 {
+        system.update_system();
+    }
+    let after = system.diagnostics();
+    assert!((after.momentum - before.momentum).length() < 1e-14);
+    let drifted = before.center_of_mass + before.momentum / before.mass * system.time;
+    assert!(after.center_of_mass.distance(drifted) < 1e-12);
+    assert_eq!(
+        integrator_from_name("wisdom-holman").unwrap().name(),
+        "Wisdom-Holman"
+    );
+}